@@ -0,0 +1,385 @@
+use crate::{constants::*, player::Player, storage, units::Units};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const ACHIEVEMENTS_KEY: &str = "achievements";
+
+// what happened on the current run so far, fed from the game or the headless simulation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunStats {
+    pub distance: f32,
+    pub time: f32,
+    pub jumps: u32,
+    pub top_speed: f32,
+    // seconds off the ground, on the jump going on now and on the longest one
+    pub airtime: f32,
+    pub longest_airtime: f32,
+    pub crashed: bool,
+    was_jumping: bool,
+}
+
+impl RunStats {
+    // once per step, after the ride moved on. a rewound or continued run is riding again
+    pub fn observe(&mut self, player: &Player, distance: f32, round_time: f32) {
+        self.crashed = false;
+        if player.is_jumping && !self.was_jumping {
+            self.jumps += 1;
+        }
+        self.was_jumping = player.is_jumping;
+        self.airtime = if player.is_jumping {
+            self.airtime + TIMESTEP
+        } else {
+            0.
+        };
+        self.longest_airtime = self.longest_airtime.max(self.airtime);
+        self.top_speed = self.top_speed.max(player.speed);
+        self.distance = distance;
+        self.time = round_time;
+    }
+
+    pub fn crash(&mut self) {
+        self.crashed = true;
+    }
+
+    // whole day and night cycles ridden, a night counts once the morning after comes
+    pub fn day_night_cycles(&self) -> u32 {
+        (self.time / DAY_NIGHT_CYCLE_TIME).floor() as u32
+    }
+}
+
+// unlock conditions as written in assets/achievements.json, like {"distance": 1000}
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    // meters in one run
+    Distance(f32),
+    // jumps in one run
+    Jumps(u32),
+    // whole day and night cycles ridden in one run
+    DayNightCycles(u32),
+    // a crash this many seconds or less into a run
+    CrashWithin(f32),
+    // km/h, whatever units the speedometer shows
+    Speed(f32),
+}
+
+impl Condition {
+    pub fn is_met(&self, stats: &RunStats) -> bool {
+        match *self {
+            Condition::CrashWithin(seconds) => stats.crashed && stats.time <= seconds,
+            _ => self.progress(stats).is_some_and(|progress| progress >= 1.),
+        }
+    }
+
+    // whether the gallery can show how close the player got
+    pub fn has_progress(&self) -> bool {
+        !matches!(self, Condition::CrashWithin(_))
+    }
+
+    // how far along the run got, from 0 to 1. none for conditions that aren't a count
+    pub fn progress(&self, stats: &RunStats) -> Option<f32> {
+        let (value, target) = match *self {
+            Condition::Distance(meters) => (stats.distance, meters),
+            Condition::Jumps(jumps) => (stats.jumps as f32, jumps as f32),
+            Condition::DayNightCycles(cycles) => (stats.day_night_cycles() as f32, cycles as f32),
+            Condition::Speed(kmh) => (Units::Metric.speed(stats.top_speed), kmh),
+            Condition::CrashWithin(_) => return None,
+        };
+        Some((value / target.max(f32::EPSILON)).min(1.))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Achievement {
+    // names the strings achievements.<id> and achievements.<id>.description
+    pub id: String,
+    pub condition: Condition,
+}
+
+impl Achievement {
+    pub fn name_key(&self) -> String {
+        format!("achievements.{}", self.id)
+    }
+
+    pub fn description_key(&self) -> String {
+        format!("achievements.{}.description", self.id)
+    }
+}
+
+// every achievement there is, in the order the gallery shows them
+pub fn definitions() -> Vec<Achievement> {
+    serde_json::from_str(ACHIEVEMENTS).unwrap_or_else(|e| {
+        macroquad::logging::error!("parsing achievements failed: {}", e);
+        Vec::new()
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Unlock {
+    pub id: String,
+    // unix seconds
+    pub time: f64,
+}
+
+// what the player has earned so far and the best progress towards the rest
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AchievementRecord {
+    pub unlocked: Vec<Unlock>,
+    pub progress: HashMap<String, f32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub id: String,
+    pub time_left: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Achievements {
+    pub definitions: Vec<Achievement>,
+    pub record: AchievementRecord,
+    pub toasts: Vec<Toast>,
+}
+
+impl Achievements {
+    pub fn new(definitions: Vec<Achievement>, record: AchievementRecord) -> Self {
+        Self {
+            definitions,
+            record,
+            toasts: Vec::new(),
+        }
+    }
+
+    pub fn load() -> Self {
+        let record = storage::load(ACHIEVEMENTS_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self::new(definitions(), record)
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save(
+            ACHIEVEMENTS_KEY,
+            &serde_json::to_string(&self.record).unwrap(),
+        ) {
+            macroquad::logging::error!("saving achievements failed: {}", e);
+        }
+    }
+
+    pub fn unlock(&self, id: &str) -> Option<&Unlock> {
+        self.record.unlocked.iter().find(|unlock| unlock.id == id)
+    }
+
+    pub fn progress(&self, id: &str) -> f32 {
+        self.record.progress.get(id).copied().unwrap_or(0.)
+    }
+
+    // unlocks whatever the run has earned and keeps the best progress on the rest, in memory only.
+    // returns the ids unlocked just now
+    pub fn evaluate(&mut self, stats: &RunStats, now: f64) -> Vec<String> {
+        let mut unlocked = Vec::new();
+        for achievement in &self.definitions {
+            if self
+                .record
+                .unlocked
+                .iter()
+                .any(|unlock| unlock.id == achievement.id)
+            {
+                continue;
+            }
+            if achievement.condition.is_met(stats) {
+                self.record.unlocked.push(Unlock {
+                    id: achievement.id.clone(),
+                    time: now,
+                });
+                self.record.progress.remove(&achievement.id);
+                unlocked.push(achievement.id.clone());
+            } else if let Some(progress) = achievement.condition.progress(stats) {
+                let best = self
+                    .record
+                    .progress
+                    .entry(achievement.id.clone())
+                    .or_insert(0.);
+                *best = best.max(progress);
+            }
+        }
+        unlocked
+    }
+
+    // evaluates during play, announcing and saving anything new
+    pub fn track(&mut self, stats: &RunStats, now: f64) {
+        let unlocked = self.evaluate(stats, now);
+        if unlocked.is_empty() {
+            return;
+        }
+        for id in unlocked {
+            self.toasts.push(Toast {
+                id,
+                time_left: ACHIEVEMENT_TOAST_TIME,
+            });
+        }
+        self.save();
+    }
+
+    // the toast showing right now, they take turns
+    pub fn toast(&self) -> Option<&Toast> {
+        self.toasts.first()
+    }
+
+    pub fn tick(&mut self, delta_time: f32) {
+        if let Some(toast) = self.toasts.first_mut() {
+            toast.time_left -= delta_time;
+            if toast.time_left <= 0. {
+                self.toasts.remove(0);
+            }
+        }
+    }
+
+    pub fn definition(&self, id: &str) -> Option<&Achievement> {
+        self.definitions
+            .iter()
+            .find(|achievement| achievement.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{input::PlayerInput, settings::Difficulty, sim::Simulation};
+
+    // rides the headless simulation a frame of one timestep at a time, jumping every so often,
+    // until the rider crashes or the time is up
+    fn ride(seconds: f32, jump_every: usize, clear_trail: bool) -> RunStats {
+        let mut sim = Simulation::new(7, Difficulty::Normal, false);
+        for i in 0..(seconds * TPS) as usize {
+            let input = PlayerInput {
+                jump: jump_every > 0 && i % jump_every == 0,
+                ..Default::default()
+            };
+            sim.tick(&input, TIMESTEP);
+            if clear_trail {
+                sim.obstacles.reset();
+            }
+            if sim.step() {
+                break;
+            }
+        }
+        sim.stats
+    }
+
+    fn crash() -> RunStats {
+        let stats = ride(600., 45, false);
+        assert!(stats.crashed);
+        stats
+    }
+
+    #[test]
+    fn stats_follow_the_ride() {
+        let stats = ride(10., 45, true);
+        assert!(!stats.crashed);
+        assert!((stats.time - 10.).abs() < 0.01);
+        assert!(stats.distance > 0.);
+        assert!(stats.jumps > 1);
+        assert!(stats.longest_airtime > 0.);
+        assert!(stats.top_speed >= DEFAULT_PLAYER_SPEED);
+
+        let grounded = ride(10., 0, true);
+        assert_eq!(grounded.jumps, 0);
+        assert_eq!(grounded.longest_airtime, 0.);
+    }
+
+    #[test]
+    fn distance() {
+        let stats = ride(10., 0, true);
+        assert!(Condition::Distance(stats.distance).is_met(&stats));
+        let further = Condition::Distance(stats.distance * 2.);
+        assert!(!further.is_met(&stats));
+        assert!((further.progress(&stats).unwrap() - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn jumps() {
+        let stats = ride(10., 45, true);
+        assert!(Condition::Jumps(stats.jumps).is_met(&stats));
+        assert!(!Condition::Jumps(stats.jumps + 1).is_met(&stats));
+        assert_eq!(
+            Condition::Jumps(stats.jumps * 2).progress(&stats),
+            Some(0.5)
+        );
+        assert_eq!(Condition::Jumps(0).progress(&stats), Some(1.));
+    }
+
+    #[test]
+    fn day_night_cycles() {
+        let stats = ride(DAY_NIGHT_CYCLE_TIME * 0.5, 0, true);
+        assert_eq!(stats.day_night_cycles(), 0);
+        assert!(!Condition::DayNightCycles(1).is_met(&stats));
+        assert_eq!(Condition::DayNightCycles(1).progress(&stats), Some(0.));
+
+        let stats = ride(DAY_NIGHT_CYCLE_TIME + 1., 0, true);
+        assert_eq!(stats.day_night_cycles(), 1);
+        assert!(Condition::DayNightCycles(1).is_met(&stats));
+        assert!(!Condition::DayNightCycles(2).is_met(&stats));
+    }
+
+    #[test]
+    fn crash_within() {
+        let stats = crash();
+        assert!(Condition::CrashWithin(stats.time).is_met(&stats));
+        assert!(!Condition::CrashWithin(stats.time - 0.1).is_met(&stats));
+        assert_eq!(Condition::CrashWithin(stats.time).progress(&stats), None);
+        assert!(!Condition::CrashWithin(stats.time).has_progress());
+
+        // riding on after a rewind or continue isn't a crash anymore
+        let riding = ride(1., 0, true);
+        assert!(!Condition::CrashWithin(600.).is_met(&riding));
+    }
+
+    #[test]
+    fn speed() {
+        let stats = ride(10., 0, true);
+        let kmh = Units::Metric.speed(stats.top_speed);
+        assert!(Condition::Speed(kmh).is_met(&stats));
+        assert!(!Condition::Speed(kmh + 1.).is_met(&stats));
+        assert!(Condition::Speed(kmh * 2.).progress(&stats).unwrap() < 0.51);
+    }
+
+    #[test]
+    fn every_definition_parses() {
+        let definitions = definitions();
+        assert!(!definitions.is_empty());
+        for (i, achievement) in definitions.iter().enumerate() {
+            assert!(definitions[..i]
+                .iter()
+                .all(|other| other.id != achievement.id));
+        }
+    }
+
+    #[test]
+    fn unlocks_happen_once_and_progress_only_goes_up() {
+        let definitions = vec![
+            Achievement {
+                id: "far".to_string(),
+                condition: Condition::Distance(1_000_000.),
+            },
+            Achievement {
+                id: "quick".to_string(),
+                condition: Condition::CrashWithin(600.),
+            },
+        ];
+        let mut achievements = Achievements::new(definitions, AchievementRecord::default());
+        let stats = crash();
+
+        assert_eq!(achievements.evaluate(&stats, 1.), vec!["quick".to_string()]);
+        assert!(achievements.evaluate(&stats, 2.).is_empty());
+        assert_eq!(achievements.unlock("quick").unwrap().time, 1.);
+        assert!(achievements.unlock("far").is_none());
+
+        let best = achievements.progress("far");
+        assert!(best > 0.);
+        achievements.evaluate(&RunStats::default(), 3.);
+        assert_eq!(achievements.progress("far"), best);
+    }
+}
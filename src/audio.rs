@@ -0,0 +1,228 @@
+use crate::{
+    foley::{Foley, RideCues},
+    music::{AdaptiveMusic, Layer, MusicCues},
+};
+use macroquad::audio::{play_sound, stop_sound, PlaySoundParams, Sound};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Master,
+    Music,
+    Sfx,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 3] = [Channel::Master, Channel::Music, Channel::Sfx];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Channel::Master => "channel.master",
+            Channel::Music => "channel.music",
+            Channel::Sfx => "channel.sfx",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sfx {
+    Crash,
+    Jump,
+    Land,
+    Skid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SoundEvent {
+    Play(Sfx),
+    Stop(Sfx),
+    Chain,
+    Landing(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MusicState {
+    Stopped,
+    Playing,
+    Paused,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master: 1.,
+            music: 0.7,
+            sfx: 1.,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn volume(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Master => self.master,
+            Channel::Music => self.music,
+            Channel::Sfx => self.sfx,
+        }
+    }
+
+    pub fn set_volume(&mut self, channel: Channel, volume: f32) {
+        let volume = volume.clamp(0., 1.);
+        match channel {
+            Channel::Master => self.master = volume,
+            Channel::Music => self.music = volume,
+            Channel::Sfx => self.sfx = volume,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sounds {
+    pub crash: Sound,
+    pub jump: Sound,
+    pub land: Sound,
+    pub skid: Sound,
+    pub music_layers: Vec<(Layer, Sound)>,
+    pub stinger: Sound,
+    pub foley: Foley,
+}
+
+#[derive(Debug, Clone)]
+pub struct Audio {
+    sounds: Sounds,
+    music: AdaptiveMusic,
+    cues: MusicCues,
+    pub settings: AudioSettings,
+    pub music_state: MusicState,
+}
+
+impl Audio {
+    pub fn new(sounds: Sounds, settings: AudioSettings) -> Self {
+        let music = AdaptiveMusic::new(sounds.music_layers.clone(), sounds.stinger);
+        Self {
+            sounds,
+            music,
+            cues: MusicCues::default(),
+            settings,
+            music_state: MusicState::Stopped,
+        }
+    }
+
+    fn sound(&self, sfx: Sfx) -> Sound {
+        match sfx {
+            Sfx::Crash => self.sounds.crash,
+            Sfx::Jump => self.sounds.jump,
+            Sfx::Land => self.sounds.land,
+            Sfx::Skid => self.sounds.skid,
+        }
+    }
+
+    pub fn volume(&self, channel: Channel) -> f32 {
+        self.settings.volume(channel)
+    }
+
+    // what the channel actually plays at after master and mute are applied
+    pub fn effective_volume(&self, channel: Channel) -> f32 {
+        if self.settings.muted {
+            return 0.;
+        }
+        match channel {
+            Channel::Master => self.settings.master,
+            _ => self.settings.master * self.volume(channel),
+        }
+    }
+
+    pub fn handle(&mut self, event: SoundEvent) {
+        match event {
+            SoundEvent::Play(sfx) => self.play(sfx),
+            SoundEvent::Stop(sfx) => self.stop(sfx),
+            SoundEvent::Chain => self
+                .sounds
+                .foley
+                .play_chain(self.effective_volume(Channel::Sfx) * 0.5),
+            SoundEvent::Landing(fall_velocity) => self
+                .sounds
+                .foley
+                .play_landing(fall_velocity, self.effective_volume(Channel::Sfx)),
+        }
+    }
+
+    pub fn play(&mut self, sfx: Sfx) {
+        play_sound(
+            self.sound(sfx),
+            PlaySoundParams {
+                looped: false,
+                volume: self.effective_volume(Channel::Sfx),
+            },
+        );
+    }
+
+    pub fn stop(&mut self, sfx: Sfx) {
+        stop_sound(self.sound(sfx));
+    }
+
+    pub fn stop_effects(&mut self) {
+        for sfx in [Sfx::Crash, Sfx::Jump, Sfx::Land, Sfx::Skid] {
+            self.stop(sfx);
+        }
+    }
+
+    pub fn play_music(&mut self) {
+        if self.music_state == MusicState::Stopped {
+            let volume = self.effective_volume(Channel::Music);
+            self.music.start(&self.cues, volume);
+        }
+        self.music_state = MusicState::Playing;
+    }
+
+    // macroquad can't pause a sound, so a paused track keeps running ducked
+    pub fn pause_music(&mut self) {
+        if self.music_state == MusicState::Playing {
+            self.music_state = MusicState::Paused;
+        }
+    }
+
+    pub fn stop_music(&mut self) {
+        if self.music_state != MusicState::Stopped {
+            self.music.stop();
+            self.music_state = MusicState::Stopped;
+        }
+    }
+
+    pub fn play_stinger(&self) {
+        self.music
+            .play_stinger(self.effective_volume(Channel::Music));
+    }
+
+    pub fn start_ride(&mut self) {
+        self.sounds.foley.start();
+    }
+
+    pub fn stop_ride(&mut self) {
+        self.sounds.foley.stop();
+    }
+
+    pub fn update_ride(&self, cues: &RideCues) {
+        self.sounds
+            .foley
+            .update(cues, self.effective_volume(Channel::Sfx));
+    }
+
+    pub fn update(&mut self, cues: MusicCues, delta_time: f32) {
+        self.cues = MusicCues {
+            is_paused: self.music_state == MusicState::Paused,
+            ..cues
+        };
+        let volume = self.effective_volume(Channel::Music);
+        self.music.update(&self.cues, volume, delta_time);
+    }
+}
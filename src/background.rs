@@ -1,7 +1,7 @@
-use crate::{constants::*, shaders::*, util::*};
+use crate::{constants::*, rng::Rng, shaders::*, util::*};
+use lerp::Lerp;
 use macroquad::prelude::*;
 use std::f32::consts::TAU;
-use lerp::Lerp;
 
 #[derive(Debug, Clone, Lerp)]
 struct Sky {
@@ -49,9 +49,12 @@ pub struct Background {
     pub celestial_body_color: Vec3,
 }
 
-impl Default for Background {
-    fn default() -> Self {
-        let rgba_texture = rgba_texture(256, 256);
+const NOISE_SIZE: u16 = 256;
+
+impl Background {
+    // the mountains are shaped by the noise, so a seed gives the same skyline every time
+    pub fn new(seed: u64) -> Self {
+        let rgba_texture = rgba_texture(NOISE_SIZE, NOISE_SIZE, &mut Rng::new(seed));
         Self {
             sky: Sky::default(),
             material: get_background_material(),
@@ -60,6 +63,11 @@ impl Default for Background {
             celestial_body_color: vec3(0., 0., 0.),
         }
     }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.rgba_texture
+            .update(&noise_image(NOISE_SIZE, NOISE_SIZE, &mut Rng::new(seed)));
+    }
 }
 
 struct CelestialBody {
@@ -130,6 +138,7 @@ impl Background {
         time: f32,
         world_time: f32,
         resolution: Vec2,
+        view: Rect,
         cycle_time: f32,
         player_speed: f32,
     ) -> bool {
@@ -169,7 +178,7 @@ impl Background {
         self.celestial_body_color = celestial_body.color;
 
         gl_use_material(self.material);
-        draw_rectangle(0., 0., resolution.x, resolution.y, WHITE);
+        draw_rectangle(view.x, view.y, view.w, view.h, WHITE);
         gl_use_default_material();
         celestial_body.is_night
     }
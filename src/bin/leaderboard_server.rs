@@ -0,0 +1,393 @@
+// a local stand-in for the online leaderboard, speaking the protocol in docs/leaderboard.md
+//
+//     cargo run --bin leaderboard_server -- [address] [scores.json]
+//
+// runs are only accepted when verify::verify rides their replay to the same crash
+use mtb::{
+    leaderboard::{
+        Accepted, Entry, Rejection, Submission, TopScores, DEFAULT_LIMIT, PROTOCOL_VERSION,
+    },
+    settings::Difficulty,
+    verify::{verify, VerifyError},
+};
+use serde::Serialize;
+use std::{
+    env, fs,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    process,
+    time::Duration,
+};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8787";
+const MAX_LIMIT: usize = 100;
+const MAX_BODY: usize = 16 * 1024 * 1024;
+const MAX_NAME: usize = 24;
+// requests are served one at a time, a client that goes quiet can't hold up the rest for longer
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    body: String,
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        Self {
+            status,
+            body: serde_json::to_string(value).unwrap(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(
+            status,
+            &Rejection {
+                error: message.to_string(),
+            },
+        )
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        _ => "Internal Server Error",
+    }
+}
+
+struct Server {
+    entries: Vec<Entry>,
+    // where accepted runs are kept between restarts, in memory only without one
+    path: Option<PathBuf>,
+}
+
+impl Server {
+    fn load(path: Option<PathBuf>) -> Self {
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self { entries, path }
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = fs::write(path, serde_json::to_string(&self.entries).unwrap()) {
+                eprintln!("saving {} failed: {}", path.display(), e);
+            }
+        }
+    }
+
+    fn handle(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let response = match read_request(&mut reader)? {
+            Some(request) => {
+                let response = self.route(&request);
+                println!(
+                    "{} {} {} -> {}",
+                    request.method, request.path, request.query, response.status
+                );
+                response
+            }
+            None => Response::error(413, "request too large"),
+        };
+        write_response(stream, &response)
+    }
+
+    fn route(&mut self, request: &Request) -> Response {
+        match (request.method.as_str(), request.path.as_str()) {
+            // browsers ask before posting json to another origin
+            ("OPTIONS", _) => Response {
+                status: 204,
+                body: String::new(),
+            },
+            ("GET", "/v1/scores") => self.top(&request.query),
+            ("POST", "/v1/scores") => self.submit(&request.body),
+            _ => Response::error(404, "not found"),
+        }
+    }
+
+    fn top(&self, query: &str) -> Response {
+        let mut seed = None;
+        let mut difficulty = None;
+        let mut limit = DEFAULT_LIMIT;
+        for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            match key {
+                "seed" => seed = value.parse::<u64>().ok(),
+                "difficulty" => {
+                    difficulty = serde_json::from_str::<Difficulty>(&format!("\"{}\"", value)).ok()
+                }
+                "limit" => limit = value.parse().unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT),
+                _ => {}
+            }
+        }
+        if seed.is_none() && difficulty.is_none() {
+            return Response::error(400, "ask for a seed or a difficulty");
+        }
+
+        let mut scores: Vec<Entry> = self
+            .entries
+            .iter()
+            .filter(|entry| seed.is_none_or(|seed| entry.seed == seed))
+            .filter(|entry| difficulty.is_none_or(|difficulty| entry.difficulty == difficulty))
+            .cloned()
+            .collect();
+        scores.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+        scores.truncate(limit);
+        Response::json(200, &TopScores { scores })
+    }
+
+    fn submit(&mut self, body: &str) -> Response {
+        let submission: Submission = match serde_json::from_str(body) {
+            Ok(submission) => submission,
+            Err(e) => return Response::error(400, &format!("malformed submission: {}", e)),
+        };
+        if submission.version != PROTOCOL_VERSION {
+            return Response::error(400, "unsupported protocol version");
+        }
+        let name = submission.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME {
+            return Response::error(400, "names are 1 to 24 characters");
+        }
+
+        // the game sends a run again when the answer got lost, it's on the board already
+        let replay = &submission.replay;
+        if let Some(entry) = self.entries.iter().find(|entry| {
+            entry.name == name
+                && entry.seed == replay.seed
+                && entry.distance.to_bits() == submission.distance.to_bits()
+                && entry.time.to_bits() == submission.time.to_bits()
+        }) {
+            return Response::json(
+                200,
+                &Accepted {
+                    rank: self.rank(entry.seed, entry.distance),
+                },
+            );
+        }
+
+        let outcome = match verify(submission.distance, submission.time, replay) {
+            Ok(outcome) => outcome,
+            Err(e @ VerifyError::UnsupportedVersion(_)) => {
+                return Response::error(400, &e.to_string())
+            }
+            Err(e) => return Response::error(422, &e.to_string()),
+        };
+
+        let rank = self.rank(replay.seed, outcome.distance);
+        self.entries.push(Entry {
+            name: name.to_string(),
+            seed: replay.seed,
+            difficulty: replay.difficulty,
+            stamina_mode: replay.stamina_mode,
+            distance: outcome.distance,
+            time: outcome.time,
+        });
+        self.save();
+        Response::json(201, &Accepted { rank })
+    }
+
+    fn rank(&self, seed: u64, distance: f32) -> usize {
+        1 + self
+            .entries
+            .iter()
+            .filter(|entry| entry.seed == seed && entry.distance > distance)
+            .count()
+    }
+}
+
+// none when the body is larger than anyone should send
+fn read_request(reader: &mut impl BufRead) -> io::Result<Option<Request>> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let (path, query) = (path.to_string(), query.to_string());
+
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        return Ok(None);
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(Request {
+        method,
+        path,
+        query,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    }))
+}
+
+fn write_response(mut stream: TcpStream, response: &Response) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.0 {} {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Content-Type\r\n\
+         \r\n{}",
+        response.status,
+        reason(response.status),
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    let mut server = Server::load(args.next().map(PathBuf::from));
+
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("can't listen on {}: {}", address, e);
+            process::exit(1);
+        }
+    };
+    println!("leaderboard listening on http://{}", address);
+    serve(&listener, &mut server);
+}
+
+// one connection at a time is plenty for a stand-in
+fn serve(listener: &TcpListener, server: &mut Server) {
+    for stream in listener.incoming() {
+        match stream.and_then(|stream| server.handle(stream)) {
+            Ok(()) => {}
+            Err(e) => eprintln!("request failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mtb::{constants::TIMESTEP, input::PlayerInput, replay::Replay, sim::Simulation};
+    use std::{io::Read, thread};
+
+    fn start() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || serve(&listener, &mut Server::load(None)));
+        address
+    }
+
+    // sends one request and returns the status and body
+    fn request(address: &str, method: &str, target: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.0\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            target,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, body.to_string())
+    }
+
+    // a run ridden on the headless simulation until it crashes
+    fn submission(seed: u64) -> Submission {
+        let mut replay = Replay::new(seed, Difficulty::Normal, false);
+        let mut sim = Simulation::new(seed, Difficulty::Normal, false);
+        for i in 0..20_000 {
+            let input = PlayerInput {
+                jump: i % 45 == 0,
+                ..Default::default()
+            };
+            replay.record_frame(&input, TIMESTEP);
+            sim.tick(&input, TIMESTEP);
+            replay.record_step();
+            if sim.step() {
+                break;
+            }
+        }
+        Submission::new("RIDER", sim.distance, sim.round_time, replay)
+    }
+
+    #[test]
+    fn a_run_sent_twice_is_on_the_board_once() {
+        let address = start();
+        let submission = submission(3);
+        let body = serde_json::to_string(&submission).unwrap();
+
+        let (status, accepted) = request(&address, "POST", "/v1/scores", &body);
+        assert_eq!(status, 201);
+        assert_eq!(serde_json::from_str::<Accepted>(&accepted).unwrap().rank, 1);
+        // the retry after a lost response gets the same answer without a second entry
+        let (status, accepted) = request(&address, "POST", "/v1/scores", &body);
+        assert_eq!(status, 200);
+        assert_eq!(serde_json::from_str::<Accepted>(&accepted).unwrap().rank, 1);
+
+        let (status, top) = request(&address, "GET", "/v1/scores?seed=3", "");
+        assert_eq!(status, 200);
+        let scores = serde_json::from_str::<TopScores>(&top).unwrap().scores;
+        assert_eq!(scores.len(), 1);
+        assert_eq!(scores[0].name, "RIDER");
+        assert_eq!(scores[0].distance.to_bits(), submission.distance.to_bits());
+        assert_eq!(scores[0].time.to_bits(), submission.time.to_bits());
+    }
+
+    #[test]
+    fn a_quiet_client_does_not_hold_up_the_rest() {
+        let address = start();
+        let _quiet = TcpStream::connect(&address).unwrap();
+        let (status, _) = request(&address, "GET", "/v1/scores?seed=1", "");
+        assert_eq!(status, 200);
+    }
+
+    #[test]
+    fn a_run_that_does_not_match_its_replay_is_rejected() {
+        let address = start();
+        let mut submission = submission(5);
+        submission.distance += 1.;
+        let body = serde_json::to_string(&submission).unwrap();
+        let (status, _) = request(&address, "POST", "/v1/scores", &body);
+        assert_eq!(status, 422);
+
+        let (_, top) = request(&address, "GET", "/v1/scores?seed=5", "");
+        assert!(serde_json::from_str::<TopScores>(&top)
+            .unwrap()
+            .scores
+            .is_empty());
+    }
+}
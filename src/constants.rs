@@ -8,12 +8,46 @@ pub const TIMESTEP: f32 = 1. / TPS;
 pub const DAY_NIGHT_CYCLE_TIME: f32 = 60.;
 pub const PLAYER_SPEED_TO_OBSTACLE_SPEED: f32 = 34.;
 pub const DEFAULT_PLAYER_SPEED: f32 = 0.14;
+pub const MIN_PLAYER_SPEED: f32 = 0.07;
+pub const BRAKE_DECELERATION: f32 = 0.002;
+pub const CHECKPOINT_INTERVAL: f32 = 250.;
+pub const MAX_CONTINUES: u32 = 3;
+pub const MAX_HIGH_SCORES: usize = 10;
+pub const MAX_DAILY_SHOWN: usize = 7;
+pub const MAX_ONLINE_SHOWN: usize = 5;
+pub const REWIND_SECONDS: f32 = 5.;
+pub const REWIND_METER: f32 = 3.;
+pub const REWIND_RECOVERY: f32 = 0.05;
+pub const RESUME_COUNTDOWN: f32 = 3.;
+pub const SUSPEND_FRAME_TIME: f32 = 1.;
+pub const MUSIC_FULL_SPEED: f32 = 0.5;
+pub const MUSIC_FADE_SPEED: f32 = 0.5;
+pub const MUSIC_PAUSED_DUCK: f32 = 0.3;
+pub const MUSIC_DANGER_DISTANCE: f32 = 600.;
+pub const MUSIC_TEMPO: f32 = 120.;
+pub const MUSIC_STEM_BARS: usize = 4;
+pub const QUICK_SAVE_SLOT: &str = "quick";
+pub const AUTOSAVE_SLOT: &str = "autosave";
+pub const MAX_STAMINA: f32 = 1.;
+pub const STAMINA_DRAIN: f32 = 0.004;
+pub const STAMINA_RECOVERY: f32 = 0.002;
+pub const PEDAL_STROKE: f32 = 0.35;
+pub const PEDAL_ACCELERATION: f32 = 0.0004;
+pub const CADENCE_DECAY: f32 = 0.97;
+pub const COAST_DRAG: f32 = 0.0008;
+pub const MAX_PARTY_SIZE: usize = 4;
+pub const PARTY_STAGGER: f32 = 200.;
+pub const ACHIEVEMENT_TOAST_TIME: f32 = 3.;
+pub const DEATH_HISTOGRAM_BUCKET: f32 = 250.;
+pub const PROFILE_HISTOGRAM_ROWS: usize = 8;
+pub const PROFILE_HISTOGRAM_WIDTH: usize = 20;
 
 pub const DEFAULT_VERTEX_SHADER: &str = include_path_str!("./shaders/default.vert.glsl");
 pub const BACKGROUND_FRAGMENT_SHADER: &str = include_path_str!("./shaders/background.frag.glsl");
 pub const POST_PROCESSSING_FRAGMENT_SHADER: &str = include_path_str!("./shaders/postprocessing.frag.glsl");
 
 pub const ROCK: &[u8] = include_path_bytes!("../assets/rock.png");
+pub const ACHIEVEMENTS: &str = include_path_str!("../assets/achievements.json");
 
 pub const VIGNETTE_RADIUS: f32 = 0.95;
 pub const VIGNETTE_SMOOTHNESS: f32 = 0.9;
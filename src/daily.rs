@@ -0,0 +1,128 @@
+use crate::storage;
+use serde::{Deserialize, Serialize};
+
+const DAILY_KEY: &str = "daily";
+const MAX_DAILY_HISTORY: usize = 30;
+const SECONDS_PER_DAY: f64 = 86_400.;
+
+// days since 1970-01-01 in utc, so everyone gets the same day at the same moment
+pub fn utc_day(unix_time: f64) -> i64 {
+    (unix_time / SECONDS_PER_DAY).floor() as i64
+}
+
+// proleptic gregorian calendar date for a day number, as yyyy-mm-dd
+pub fn date_string(day: i64) -> String {
+    let z = day + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day_of_month)
+}
+
+// splitmix64 over the day number, neighbouring days end up with unrelated trails
+pub fn seed(day: i64) -> u64 {
+    let mut z = (day as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub day: i64,
+    pub distance: f32,
+    pub time: f32,
+    pub assisted: bool,
+}
+
+// kept apart from the high scores, one entry per day, newest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyHistory {
+    // the attempt is used up the moment it starts, quitting doesn't buy a retry
+    pub last_attempt: Option<i64>,
+    pub results: Vec<DailyResult>,
+}
+
+impl DailyHistory {
+    pub fn load() -> Self {
+        storage::load(DAILY_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save(DAILY_KEY, &serde_json::to_string(self).unwrap()) {
+            macroquad::logging::error!("saving daily history failed: {}", e);
+        }
+    }
+
+    pub fn has_attempted(&self, day: i64) -> bool {
+        self.last_attempt == Some(day)
+    }
+
+    pub fn result(&self, day: i64) -> Option<&DailyResult> {
+        self.results.iter().find(|result| result.day == day)
+    }
+
+    pub fn start(&mut self, day: i64) {
+        self.last_attempt = Some(day);
+        self.save();
+    }
+
+    pub fn record(&mut self, result: DailyResult) {
+        if self.result(result.day).is_some() {
+            return;
+        }
+        self.results.insert(0, result);
+        self.results.truncate(MAX_DAILY_HISTORY);
+        self.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_day_turns_over_at_utc_midnight() {
+        assert_eq!(utc_day(0.), 0);
+        assert_eq!(utc_day(-1.), -1);
+        assert_eq!(utc_day(1_704_067_199.), 19_722);
+        assert_eq!(utc_day(1_704_067_199.999), 19_722);
+        assert_eq!(utc_day(1_704_067_200.), 19_723);
+        assert_eq!(date_string(0), "1970-01-01");
+        assert_eq!(date_string(-1), "1969-12-31");
+        assert_eq!(date_string(19_722), "2023-12-31");
+        assert_eq!(date_string(19_723), "2024-01-01");
+    }
+
+    #[test]
+    fn leap_days_get_a_date_of_their_own() {
+        assert_eq!(date_string(utc_day(1_709_208_000.)), "2024-02-29");
+        assert_eq!(date_string(19_783), "2024-03-01");
+        assert_eq!(date_string(utc_day(951_782_400.)), "2000-02-29");
+        // not a leap year, a century that isn't divisible by 400
+        assert_eq!(date_string(utc_day(4_107_456_000.)), "2100-02-28");
+        assert_eq!(date_string(47_541), "2100-03-01");
+    }
+
+    #[test]
+    fn every_platform_rides_the_same_trail_on_the_same_day() {
+        // splitmix64's first output from a zero state
+        assert_eq!(seed(0), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(seed(19_782), 0xdb8c_04ca_25f6_e90d);
+        assert_eq!(seed(19_783), 0x1137_79ac_668e_2fcd);
+        assert_eq!(seed(utc_day(1_709_164_800.)), seed(utc_day(1_709_251_199.)));
+    }
+}
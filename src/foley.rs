@@ -0,0 +1,152 @@
+use crate::{constants::*, synth};
+use macroquad::audio::{
+    load_sound_from_bytes, play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound,
+};
+
+const TIRE_SPEEDS: [f32; 3] = [0.1, 0.3, 0.6];
+const LANDING_VARIATIONS: usize = 4;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RideCues {
+    pub speed: f32,
+    pub is_moving: bool,
+    pub is_jumping: bool,
+    pub vertical_velocity: f32,
+}
+
+async fn load(samples: &[f32]) -> Sound {
+    load_sound_from_bytes(&synth::to_wav(samples))
+        .await
+        .unwrap()
+}
+
+// continuous ride sounds synthesized at startup and mixed by volume
+#[derive(Debug, Clone)]
+pub struct Foley {
+    tires: Vec<Sound>,
+    wind: Sound,
+    freewheel: Sound,
+    chain: Sound,
+    landings: Vec<Sound>,
+    is_playing: bool,
+}
+
+impl Foley {
+    pub async fn load() -> Self {
+        let mut tires = Vec::with_capacity(TIRE_SPEEDS.len());
+        for (i, speed) in TIRE_SPEEDS.iter().enumerate() {
+            tires.push(load(&synth::tire_roll(*speed, 2., i as u64)).await);
+        }
+
+        let mut landings = Vec::with_capacity(LANDING_VARIATIONS);
+        for i in 0..LANDING_VARIATIONS {
+            let intensity = (i + 1) as f32 / LANDING_VARIATIONS as f32;
+            landings.push(load(&synth::landing(intensity, i as u64)).await);
+        }
+
+        Self {
+            tires,
+            wind: load(&synth::wind(3., 7)).await,
+            freewheel: load(&synth::freewheel(24., 1., 11)).await,
+            chain: load(&synth::chain_tick(13)).await,
+            landings,
+            is_playing: false,
+        }
+    }
+
+    fn loops(&self) -> impl Iterator<Item = Sound> + '_ {
+        self.tires
+            .iter()
+            .copied()
+            .chain([self.wind, self.freewheel])
+    }
+
+    pub fn start(&mut self) {
+        if self.is_playing {
+            return;
+        }
+        for sound in self.loops() {
+            play_sound(
+                sound,
+                PlaySoundParams {
+                    looped: true,
+                    volume: 0.,
+                },
+            );
+        }
+        self.is_playing = true;
+    }
+
+    pub fn stop(&mut self) {
+        if !self.is_playing {
+            return;
+        }
+        for sound in self.loops() {
+            stop_sound(sound);
+        }
+        self.is_playing = false;
+    }
+
+    pub fn update(&self, cues: &RideCues, volume: f32) {
+        if !self.is_playing {
+            return;
+        }
+
+        // crossfade between the neighbouring pre-rendered tire loops
+        let on_ground = cues.is_moving && !cues.is_jumping;
+        for (i, tire) in self.tires.iter().enumerate() {
+            let weight = if on_ground {
+                tire_weight(i, cues.speed)
+            } else {
+                0.
+            };
+            set_sound_volume(*tire, weight * volume);
+        }
+
+        let airtime = cues.is_moving && cues.is_jumping;
+        let wind = if airtime {
+            (0.4 + cues.vertical_velocity.abs() / *JUMP_FORCE).min(1.)
+        } else {
+            0.
+        };
+        set_sound_volume(self.wind, wind * volume);
+        set_sound_volume(self.freewheel, if airtime { 0.6 * volume } else { 0. });
+    }
+
+    pub fn play_chain(&self, volume: f32) {
+        play_sound(
+            self.chain,
+            PlaySoundParams {
+                looped: false,
+                volume,
+            },
+        );
+    }
+
+    pub fn play_landing(&self, fall_velocity: f32, volume: f32) {
+        let intensity = (fall_velocity / *JUMP_FORCE).clamp(0., 1.);
+        let i = ((intensity * LANDING_VARIATIONS as f32) as usize).min(LANDING_VARIATIONS - 1);
+        play_sound(
+            self.landings[i],
+            PlaySoundParams {
+                looped: false,
+                volume: volume * (0.5 + 0.5 * intensity),
+            },
+        );
+    }
+}
+
+fn tire_weight(i: usize, speed: f32) -> f32 {
+    let last = TIRE_SPEEDS.len() - 1;
+    let speed = speed.clamp(TIRE_SPEEDS[0], TIRE_SPEEDS[last]);
+    let distance = |j: usize| (speed - TIRE_SPEEDS[j]).abs();
+    let neighbour = if i > 0 && speed < TIRE_SPEEDS[i] {
+        i - 1
+    } else if i < last && speed > TIRE_SPEEDS[i] {
+        i + 1
+    } else {
+        return if distance(i) == 0. { 1. } else { 0. };
+    };
+    let span = (TIRE_SPEEDS[neighbour] - TIRE_SPEEDS[i]).abs();
+    (1. - distance(i) / span).max(0.)
+}
@@ -1,20 +1,78 @@
 use crate::{
+    achievements::{Achievements, RunStats},
+    audio::{Audio, Sfx, Sounds},
     background::Background,
     constants::*,
+    daily::{self, DailyHistory, DailyResult},
+    foley::RideCues,
+    ghost::{Ghost, GhostRace},
+    hud::Hud,
+    input::{Action, PlayerInput},
+    leaderboard::{Board, Leaderboard, Status, Submission},
+    locale::Locale,
+    markers::TrailMarkers,
+    music::MusicCues,
     obstacles::{ObstaclePool, ObstaclePoolSettings},
+    options::OptionsMenu,
+    party::Party,
     player::Player,
+    profile::Profile,
+    replay::Replay,
+    rewind::Rewind,
+    rng::Rng,
+    scores::{HighScores, Score},
+    settings::{Difficulty, DisplayMode, PostProcessing, Settings},
     shaders::get_post_processing_material,
+    sim,
+    snapshot::Snapshot,
+    storage,
+    ui::{Menu, UiEvent},
+    versus::Versus,
+    viewport::{ScaleMode, Viewport},
 };
-use macroquad::{
-    audio::{play_sound, play_sound_once, stop_sound, PlaySoundParams, Sound},
-    prelude::*,
-};
+use macroquad::{miniquad::date, prelude::*};
 
-#[derive(PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum GameState {
+    Title,
     Running,
+    Countdown,
     Paused,
     GameOver,
+    Rewinding,
+    Options,
+    HighScores,
+    Versus,
+    VersusResults,
+    Party,
+    PartyResults,
+    Achievements,
+    Profile,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuItem {
+    ResumeRide,
+    Start,
+    Daily,
+    Versus,
+    Party,
+    PartySize,
+    StaminaMode,
+    Checkpoints,
+    HighScores,
+    Achievements,
+    Profile,
+    ExportProfile,
+    Options,
+    Quit,
+    Resume,
+    Continue,
+    Restart,
+    MainMenu,
+    Back,
+    ImportGhost,
+    ExportGhost,
 }
 
 #[derive(Clone)]
@@ -28,85 +86,1126 @@ pub struct Game {
     pub obstacles: ObstaclePool,
     pub spawn_time: f64,
     pub state: GameState,
+    pub options_return: GameState,
+    pub countdown: f32,
+    pub auto_paused: bool,
+    // a ride saved when the game was last hidden or closed, offered again on the title screen
+    pub has_autosave: bool,
     pub round_time: f32,
     pub resolution: Vec2,
     pub post_processing_material: Material,
     pub game_render_target: RenderTarget,
     pub camera: Camera2D,
+    pub viewport: Viewport,
+    pub hud: Hud,
     pub day_night_cycle_time: f32,
     pub world_time: f32,
+    pub is_night: bool,
     pub rock: Texture2D,
     pub tree: Texture2D,
-    pub crash_sound: Sound,
-    pub jump_sound: Sound,
-    pub land_sound: Sound,
-    pub music: Sound,
-    pub is_playing_music: bool,
+    pub markers: TrailMarkers,
+    pub audio: Audio,
+    pub settings: Settings,
+    pub locale: Locale,
+    pub options: OptionsMenu,
+    pub title_menu: Menu<MenuItem>,
+    pub pause_menu: Menu<MenuItem>,
+    pub high_scores_menu: Menu<MenuItem>,
+    pub achievements_menu: Menu<MenuItem>,
+    pub profile_menu: Menu<MenuItem>,
+    pub game_over_menu: Menu<MenuItem>,
+    pub rng: Rng,
+    // every run on the same seed gets the same trail
+    pub seed: u64,
+    // races draw their trails from here, the solo run's rng has to stay where its replay expects it
+    pub race_rng: Rng,
+    pub ghosts: GhostRace,
+    pub rival: Option<Ghost>,
+    // feedback from exporting and importing ghosts
+    pub notice: Option<String>,
+    // the utc day of the scored daily challenge being ridden
+    pub daily: Option<i64>,
+    pub daily_history: DailyHistory,
+    // the inputs of the current run, submitted with its score
+    pub replay: Replay,
+    pub leaderboard: Leaderboard,
+    pub versus: Versus,
+    pub party: Party,
+    // how many riders share the trail in a party ride
+    pub party_size: usize,
+    pub race_menu: Menu<MenuItem>,
+    pub checkpoints_enabled: bool,
+    pub checkpoint: Option<Snapshot>,
+    pub next_checkpoint: f32,
+    pub continues_left: u32,
+    pub assisted: bool,
+    pub high_scores: HighScores,
+    pub rewind: Rewind,
+    pub achievements: Achievements,
+    // what the current run has done towards the achievements
+    pub run_stats: RunStats,
+    pub profile: Profile,
+    // how much of the current run the profile already has, it goes on after a rewind
+    pub run_recorded: RunStats,
 }
 
 impl Game {
     pub fn new(
         rock: Texture2D,
         tree: Texture2D,
-        crash: Sound,
-        jump: Sound,
-        land: Sound,
-        music: Sound,
+        sounds: Sounds,
+        settings: Settings,
+        mut locale: Locale,
     ) -> Self {
         let size = vec2(RESOLUTION_X, RESOLUTION_Y);
-        let rect = Rect::new(0., 0., size.x, size.y);
-        let mut camera = Camera2D::from_display_rect(rect);
-        let game_render_target = render_target(size.x as u32, size.y as u32);
-        game_render_target.texture.set_filter(FilterMode::Linear);
-        camera.render_target = Some(game_render_target);
+        let viewport = Viewport::new(size, settings.scale_mode);
+        let (game_render_target, camera) =
+            game_camera(viewport.game_view(), scale_filter(settings.scale_mode));
+        locale.select(settings.language);
+        let seed = date::now() as u64;
+        let ghosts = GhostRace::new(Player::new(vec2(128., 128.), size));
+        let replay = Replay::new(seed, settings.difficulty, false);
+        let leaderboard = Leaderboard::load(settings.leaderboard_url.clone());
+        let versus = Versus::new(scale_filter(settings.scale_mode));
 
         Self {
             time: 0.,
             world_time: 0.,
+            is_night: false,
             delta_time: 0.,
             round_time: 0.,
-            background: Background::default(),
-            player: Player::new(vec2(128., 128.), size, jump, land),
-            obstacles: ObstaclePool::new(10, None),
-            trees: ObstaclePool::new(
-                100,
-                Some(ObstaclePoolSettings {
-                    base_spawn_chance: 0.8,
-                    spawn_interval: 10.,
-                    base_size: vec2(343., 500.),
-                    max_size: vec2(343., 500.),
-                    ..Default::default()
-                }),
-            ),
+            background: Background::new(seed),
+            player: Player::new(vec2(128., 128.), size),
+            obstacles: ObstaclePool::new(10, Some(settings.difficulty.obstacle_settings())),
+            trees: ObstaclePool::new(100, Some(ObstaclePoolSettings::trees())),
             distance: 0.,
             spawn_time: 2.,
-            state: GameState::Paused,
+            state: GameState::Title,
+            options_return: GameState::Title,
+            countdown: 0.,
+            auto_paused: false,
+            has_autosave: Snapshot::load(AUTOSAVE_SLOT).is_ok(),
             resolution: size,
             post_processing_material: get_post_processing_material(),
             game_render_target,
             camera,
+            viewport,
+            hud: Hud::default(),
             day_night_cycle_time: DAY_NIGHT_CYCLE_TIME,
             rock,
             tree,
-            crash_sound: crash,
-            jump_sound: jump,
-            land_sound: land,
-            music,
-            is_playing_music: false,
+            markers: TrailMarkers::load(),
+            audio: Audio::new(sounds, settings.audio.clone()),
+            settings,
+            locale,
+            options: OptionsMenu::default(),
+            title_menu: Menu::new(),
+            pause_menu: Menu::new(),
+            high_scores_menu: Menu::new(),
+            achievements_menu: Menu::new(),
+            profile_menu: Menu::new(),
+            game_over_menu: Menu::new(),
+            rng: Rng::new(seed),
+            seed,
+            race_rng: Rng::new(!seed),
+            ghosts,
+            rival: None,
+            notice: None,
+            daily: None,
+            daily_history: DailyHistory::load(),
+            replay,
+            leaderboard,
+            versus,
+            party: Party::new(),
+            party_size: 2,
+            race_menu: Menu::new(),
+            checkpoints_enabled: false,
+            checkpoint: None,
+            next_checkpoint: CHECKPOINT_INTERVAL,
+            continues_left: MAX_CONTINUES,
+            assisted: false,
+            high_scores: HighScores::load(MAX_HIGH_SCORES),
+            rewind: Rewind::new(REWIND_SECONDS),
+            achievements: Achievements::load(),
+            run_stats: RunStats::default(),
+            profile: Profile::load(),
+            run_recorded: RunStats::default(),
         }
     }
 
     pub fn tick(&mut self) {
+        self.viewport.update(self.settings.scale_mode);
+        let texture = self.game_render_target.texture;
+        if self.viewport.game_size != vec2(texture.width(), texture.height()) {
+            self.game_render_target.delete();
+            let (game_render_target, camera) = game_camera(
+                self.viewport.game_view(),
+                scale_filter(self.settings.scale_mode),
+            );
+            self.game_render_target = game_render_target;
+            self.camera = camera;
+        }
         self.delta_time = get_frame_time();
         self.world_time += self.delta_time;
 
+        let rewind = self.settings.bindings.is_down(Action::Rewind);
+        if rewind && (self.is_running() || self.is_game_over()) {
+            if self.rewind.can_rewind() {
+                self.assisted = true;
+                self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME;
+                self.state = GameState::Rewinding;
+            }
+        } else if self.is_rewinding() && !rewind {
+            self.state = GameState::Running;
+        }
+
+        if is_key_pressed(KeyCode::F5) && self.is_riding() {
+            self.quick_save();
+        }
+
+        if is_key_pressed(KeyCode::F9) && self.is_riding() {
+            self.quick_load();
+        }
+
+        if is_key_pressed(KeyCode::M) {
+            self.settings.audio.muted = !self.settings.audio.muted;
+            self.apply_settings();
+        }
+
+        match self.state {
+            GameState::Title => self.tick_title_menu(),
+            GameState::Paused => self.tick_pause_menu(),
+            GameState::GameOver => self.tick_game_over_menu(),
+            GameState::HighScores => self.tick_high_scores_menu(),
+            GameState::Achievements => self.tick_achievements_menu(),
+            GameState::Profile => self.tick_profile_menu(),
+            GameState::Options => self.tick_options(),
+            GameState::Countdown => self.tick_countdown(),
+            GameState::Versus => self.tick_versus(),
+            GameState::VersusResults => self.tick_versus_results(),
+            GameState::Party => self.tick_party(),
+            GameState::PartyResults => self.tick_party_results(),
+            GameState::Running | GameState::Rewinding => {}
+        }
+
         if self.is_running() {
             self.time = get_time();
-            self.player.tick();
+            let input = PlayerInput::poll(&self.settings.bindings);
+            if self.round_time == 0. {
+                let difficulty = self.run_difficulty();
+                self.replay = Replay::new(self.seed, difficulty, self.player.stamina_mode);
+                self.ghosts.start(
+                    self.seed,
+                    difficulty,
+                    self.player.stamina_mode,
+                    self.rival.as_ref(),
+                );
+            }
+            self.replay.record_frame(&input, self.delta_time);
+            self.player.tick(&input);
+            self.play_player_sounds();
             self.obstacles.tick();
             self.trees.tick();
             self.round_time += self.delta_time;
         }
+        self.hud.tick(self.player.speed, self.delta_time);
+        self.achievements.tick(self.delta_time);
+        self.leaderboard.tick(get_time());
+
+        self.update_music();
+    }
+
+    fn music_cues(&self) -> MusicCues {
+        // a race plays along with whoever is in front
+        let leader = match self.state {
+            GameState::Versus => self
+                .versus
+                .leader()
+                .map(|leader| (&leader.player, &leader.obstacles)),
+            GameState::Party => self
+                .party
+                .leader()
+                .map(|leader| (&leader.player, &self.party.obstacles)),
+            _ => None,
+        };
+        let (player, obstacles) = leader.unwrap_or((&self.player, &self.obstacles));
+        let front = player.center.x + player.size.x;
+        let danger = match obstacles.distance_ahead(front) {
+            Some(distance) => 1. - (distance / MUSIC_DANGER_DISTANCE).min(1.),
+            None => 0.,
+        };
+        MusicCues {
+            speed: player.speed,
+            is_night: self.is_night,
+            danger,
+            is_paused: false,
+        }
+    }
+
+    // escape backs out of whatever screen is showing
+    pub fn back(&mut self) {
+        match self.state {
+            GameState::Running | GameState::Countdown => self.pause(),
+            GameState::Paused => self.resume(),
+            GameState::Options => {
+                self.options.open();
+                self.state = self.options_return;
+            }
+            GameState::HighScores | GameState::Achievements | GameState::Profile => {
+                self.state = GameState::Title
+            }
+            // there's no pausing a race, backing out ends it
+            GameState::Versus
+            | GameState::VersusResults
+            | GameState::Party
+            | GameState::PartyResults => self.leave_race(),
+            GameState::Title | GameState::GameOver | GameState::Rewinding => {}
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if matches!(
+            self.state,
+            GameState::Running | GameState::Countdown | GameState::Rewinding
+        ) {
+            self.pause_menu.reset_focus();
+            self.state = GameState::Paused;
+        }
+    }
+
+    // the window lost focus or the page was hidden
+    pub fn suspend(&mut self) {
+        if matches!(
+            self.state,
+            GameState::Running | GameState::Countdown | GameState::Rewinding
+        ) {
+            self.pause();
+            self.auto_paused = true;
+        }
+        // a hidden tab may well be closed next, the title screen offers the ride again
+        if self.is_paused() {
+            self.autosave();
+        }
+    }
+
+    // only rides that were paused for us carry on by themselves
+    pub fn wake(&mut self) {
+        if self.auto_paused && self.is_paused() {
+            self.resume();
+        }
+        self.auto_paused = false;
+    }
+
+    fn resume(&mut self) {
+        self.auto_paused = false;
+        self.replay.resume();
+        self.countdown = RESUME_COUNTDOWN;
+        self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME;
+        self.state = GameState::Countdown;
+    }
+
+    fn open_options(&mut self) {
+        self.options.open();
+        self.options_return = self.state;
+        self.state = GameState::Options;
+    }
+
+    fn quit(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        unsafe {
+            get_internal_gl().quad_context.quit()
+        };
+    }
+
+    fn tick_countdown(&mut self) {
+        self.countdown -= self.delta_time;
+        if self.countdown <= 0. {
+            self.countdown = 0.;
+            self.state = GameState::Running;
+        }
+    }
+
+    fn tick_options(&mut self) {
+        let display_mode = self.settings.display_mode;
+        if self
+            .options
+            .tick(&mut self.settings, &self.viewport, &self.locale)
+        {
+            if display_mode != self.settings.display_mode {
+                let fullscreen = self.settings.display_mode == DisplayMode::Fullscreen;
+                unsafe { get_internal_gl().quad_context.set_fullscreen(fullscreen) };
+            }
+            self.apply_settings();
+        }
+    }
+
+    fn tick_title_menu(&mut self) {
+        self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME / 5.;
+
+        let locale = &self.locale;
+        let on_off = |value: bool| locale.text(if value { "common.on" } else { "common.off" });
+        let menu = &mut self.title_menu;
+        menu.clear();
+        menu.label(locale.text("title.brand"), 64., PALETTE[15]);
+        menu.label(locale.text("title.name"), 96., PALETTE[15]);
+        if self.has_autosave {
+            menu.button(MenuItem::ResumeRide, locale.text("title.resume_ride"));
+        }
+        menu.button(MenuItem::Start, locale.text("title.start"));
+        let today = daily::utc_day(date::now());
+        match self.daily_history.result(today) {
+            _ if !self.daily_history.has_attempted(today) => {
+                menu.button(MenuItem::Daily, locale.text("title.daily"))
+            }
+            Some(result) => menu.label(
+                &locale.format(
+                    "title.daily_done",
+                    &[(
+                        "distance",
+                        &self.settings.units.format_distance(locale, result.distance),
+                    )],
+                ),
+                40.,
+                PALETTE[12],
+            ),
+            None => menu.label(locale.text("title.daily_used"), 40., PALETTE[12]),
+        }
+        menu.button(MenuItem::Versus, locale.text("title.versus"));
+        menu.button(MenuItem::Party, locale.text("title.party"));
+        menu.choice(
+            MenuItem::PartySize,
+            locale.text("title.party_size"),
+            &self.party_size.to_string(),
+        );
+        menu.choice(
+            MenuItem::StaminaMode,
+            locale.text("title.stamina_mode"),
+            on_off(self.player.stamina_mode),
+        );
+        menu.choice(
+            MenuItem::Checkpoints,
+            locale.text("title.checkpoints"),
+            on_off(self.checkpoints_enabled),
+        );
+        menu.button(MenuItem::HighScores, locale.text("title.high_scores"));
+        menu.button(MenuItem::Achievements, locale.text("title.achievements"));
+        menu.button(MenuItem::Profile, locale.text("title.profile"));
+        menu.button(MenuItem::ImportGhost, locale.text("title.import_ghost"));
+        menu.button(MenuItem::Options, locale.text("common.options"));
+        if cfg!(not(target_arch = "wasm32")) {
+            menu.button(MenuItem::Quit, locale.text("title.quit"));
+        }
+        menu.label(
+            locale.text(if self.settings.audio.muted {
+                "title.muted"
+            } else {
+                "title.mute"
+            }),
+            32.,
+            PALETTE[12],
+        );
+        if let Some(notice) = &self.notice {
+            menu.label(notice, 32., PALETTE[13]);
+        }
+
+        match menu.tick(&self.viewport) {
+            Some(UiEvent::Activated(MenuItem::ResumeRide)) => self.resume_autosave(),
+            Some(UiEvent::Activated(MenuItem::Start)) => {
+                self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME;
+                self.state = GameState::Running;
+            }
+            Some(UiEvent::Activated(MenuItem::Daily)) => self.start_daily(today),
+            Some(UiEvent::Activated(MenuItem::Versus)) => {
+                let seed = date::now() as u64 ^ self.race_rng.next_u32() as u64;
+                self.start_versus(seed);
+            }
+            Some(UiEvent::Activated(MenuItem::Party)) => {
+                let seed = date::now() as u64 ^ self.race_rng.next_u32() as u64;
+                self.start_party(seed);
+            }
+            Some(UiEvent::Changed(MenuItem::PartySize, delta)) => {
+                let sizes = MAX_PARTY_SIZE as i32 - 1;
+                self.party_size =
+                    ((self.party_size as i32 - 2 + delta).rem_euclid(sizes) + 2) as usize;
+            }
+            Some(UiEvent::Changed(MenuItem::StaminaMode, _)) => {
+                self.player.stamina_mode = !self.player.stamina_mode;
+                self.player.reset();
+            }
+            Some(UiEvent::Changed(MenuItem::Checkpoints, _)) => {
+                self.checkpoints_enabled = !self.checkpoints_enabled;
+            }
+            Some(UiEvent::Activated(MenuItem::HighScores)) => {
+                // once today's daily is ridden its board is the one worth a look
+                let today = daily::utc_day(date::now());
+                self.leaderboard
+                    .fetch(if self.daily_history.has_attempted(today) {
+                        Board::Seed(daily::seed(today))
+                    } else {
+                        Board::Difficulty(self.settings.difficulty)
+                    });
+                self.high_scores_menu.reset_focus();
+                self.state = GameState::HighScores;
+            }
+            Some(UiEvent::Activated(MenuItem::Achievements)) => {
+                self.achievements_menu.reset_focus();
+                self.state = GameState::Achievements;
+            }
+            Some(UiEvent::Activated(MenuItem::Profile)) => {
+                self.notice = None;
+                self.profile_menu.reset_focus();
+                self.state = GameState::Profile;
+            }
+            Some(UiEvent::Activated(MenuItem::ImportGhost)) => storage::request_import(),
+            Some(UiEvent::Activated(MenuItem::Options)) => self.open_options(),
+            Some(UiEvent::Activated(MenuItem::Quit)) => self.quit(),
+            _ => {}
+        }
+        self.import_ghosts();
+    }
+
+    // the same trail and sky for everyone on the same utc day, scored once
+    fn start_daily(&mut self, day: i64) {
+        self.rival = None;
+        self.seed = daily::seed(day);
+        self.reset();
+        // everyone rides the same obstacles, whatever difficulty they picked
+        self.obstacles
+            .configure(Difficulty::Normal.obstacle_settings());
+        self.obstacles.reset();
+        self.daily = Some(day);
+        self.daily_history.start(day);
+        self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME;
+        self.state = GameState::Running;
+    }
+
+    // both riders get the stamina mode and difficulty picked for riding alone
+    fn start_versus(&mut self, seed: u64) {
+        self.versus
+            .start(seed, self.settings.difficulty, self.player.stamina_mode);
+        self.background.reseed(seed);
+        self.world_time = 0.;
+        self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME;
+        self.state = GameState::Versus;
+    }
+
+    // shared trail rides leave stamina mode out, every rider only has a jump key
+    fn start_party(&mut self, seed: u64) {
+        self.party
+            .start(seed, self.party_size, self.settings.difficulty);
+        self.background.reseed(seed);
+        self.world_time = 0.;
+        self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME;
+        self.state = GameState::Party;
+    }
+
+    fn leave_race(&mut self) {
+        self.audio.stop_effects();
+        self.background.reseed(self.seed);
+        self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME / 5.;
+        self.title_menu.reset_focus();
+        self.state = GameState::Title;
+    }
+
+    fn tick_versus(&mut self) {
+        self.time = get_time();
+        self.versus.tick(self.delta_time);
+        self.play_race_sounds();
+    }
+
+    fn tick_party(&mut self) {
+        self.time = get_time();
+        self.party.tick(self.delta_time);
+        self.play_race_sounds();
+    }
+
+    fn tick_versus_results(&mut self) {
+        let results: Vec<_> = self
+            .versus
+            .riders
+            .iter()
+            .map(|rider| (rider.color, rider.sim.distance, rider.sim.round_time))
+            .collect();
+        match self.tick_race_results(&results) {
+            Some(UiEvent::Activated(MenuItem::Restart)) => self.start_versus(self.versus.seed),
+            Some(UiEvent::Activated(MenuItem::MainMenu)) => self.leave_race(),
+            _ => {}
+        }
+    }
+
+    fn tick_party_results(&mut self) {
+        let results: Vec<_> = self
+            .party
+            .riders
+            .iter()
+            .map(|rider| (rider.color, rider.distance, rider.time))
+            .collect();
+        match self.tick_race_results(&results) {
+            Some(UiEvent::Activated(MenuItem::Restart)) => self.start_party(self.party.seed),
+            Some(UiEvent::Activated(MenuItem::MainMenu)) => self.leave_race(),
+            _ => {}
+        }
+    }
+
+    // results holds every rider's color, distance and time, in player order
+    fn tick_race_results(&mut self, results: &[(Color, f32, f32)]) -> Option<UiEvent<MenuItem>> {
+        let locale = &self.locale;
+        let units = self.settings.units;
+        let menu = &mut self.race_menu;
+        menu.clear();
+        menu.label(locale.text("race.results"), 96., PALETTE[15]);
+        let winner = race_winner(results);
+        for (i, (color, distance, time)) in results.iter().enumerate() {
+            let number = (i + 1).to_string();
+            let distance = units.format_distance(locale, *distance);
+            let time = seconds(locale, *time);
+            let color = if winner == Some(i) {
+                *color
+            } else {
+                PALETTE[12]
+            };
+            menu.label(
+                &locale.format(
+                    "race.result",
+                    &[
+                        ("number", &number),
+                        ("distance", &distance),
+                        ("time", &time),
+                    ],
+                ),
+                40.,
+                color,
+            );
+        }
+        let verdict = match winner {
+            Some(i) => locale.format("race.wins", &[("number", &(i + 1).to_string())]),
+            None => locale.text("race.draw").to_string(),
+        };
+        menu.label(&verdict, 64., PALETTE[15]);
+        menu.button(MenuItem::Restart, locale.text("race.rematch"));
+        menu.button(MenuItem::MainMenu, locale.text("game_over.main_menu"));
+        menu.tick(&self.viewport)
+    }
+
+    fn finish_daily(&mut self) {
+        if let Some(day) = self.daily.take() {
+            self.daily_history.record(DailyResult {
+                day,
+                distance: self.distance,
+                time: self.round_time,
+                assisted: self.assisted,
+            });
+            self.notice = Some(self.locale.text("daily.recorded").to_string());
+        }
+    }
+
+    // an imported ghost becomes the rival and its seed the next trail
+    fn import_ghosts(&mut self) {
+        for json in storage::take_imports() {
+            match Ghost::from_json(&json) {
+                // racing it on another difficulty or mode would be a different trail
+                Ok(ghost)
+                    if ghost.difficulty != self.settings.difficulty
+                        || ghost.stamina_mode != self.player.stamina_mode =>
+                {
+                    self.notice = Some(self.locale.text("ghost.import_mismatch").to_string());
+                }
+                Ok(ghost) => {
+                    self.notice = Some(
+                        self.locale.format(
+                            "ghost.imported",
+                            &[(
+                                "distance",
+                                &self
+                                    .settings
+                                    .units
+                                    .format_distance(&self.locale, ghost.distance),
+                            )],
+                        ),
+                    );
+                    self.seed = ghost.seed;
+                    self.rival = Some(ghost);
+                    self.reset();
+                }
+                Err(e) => {
+                    error!("importing ghost failed: {}", e);
+                    self.notice = Some(self.locale.text("ghost.import_failed").to_string());
+                }
+            }
+        }
+    }
+
+    fn export_ghost(&mut self) {
+        let notice = match self.ghosts.best().map(|ghost| ghost.export()) {
+            Some(Ok(path)) => self.locale.format("ghost.exported", &[("path", &path)]),
+            Some(Err(e)) => {
+                error!("exporting ghost failed: {}", e);
+                self.locale.text("ghost.export_failed").to_string()
+            }
+            None => self.locale.text("ghost.export_failed").to_string(),
+        };
+        self.notice = Some(notice);
+    }
+
+    fn tick_pause_menu(&mut self) {
+        self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME / 5.;
+
+        let locale = &self.locale;
+        let menu = &mut self.pause_menu;
+        menu.clear();
+        menu.label(locale.text("pause.title"), 96., PALETTE[15]);
+        menu.button(MenuItem::Resume, locale.text("pause.resume"));
+        menu.button(MenuItem::Restart, locale.text("common.restart"));
+        menu.button(MenuItem::Options, locale.text("common.options"));
+        menu.button(MenuItem::Quit, locale.text("pause.quit"));
+
+        match menu.tick(&self.viewport) {
+            Some(UiEvent::Activated(MenuItem::Resume)) => self.resume(),
+            Some(UiEvent::Activated(MenuItem::Restart)) => {
+                self.reset();
+                self.state = GameState::Running;
+            }
+            Some(UiEvent::Activated(MenuItem::Options)) => self.open_options(),
+            Some(UiEvent::Activated(MenuItem::Quit)) => {
+                self.new_trail();
+                self.title_menu.reset_focus();
+                self.state = GameState::Title;
+            }
+            _ => {}
+        }
+    }
+
+    fn tick_high_scores_menu(&mut self) {
+        let locale = &self.locale;
+        let menu = &mut self.high_scores_menu;
+        menu.clear();
+        menu.label(locale.text("scores.title"), 96., PALETTE[15]);
+        if self.high_scores.scores().is_empty() {
+            menu.label(locale.text("scores.empty"), 48., PALETTE[12]);
+        }
+        for (i, score) in self.high_scores.scores().iter().enumerate() {
+            let entry = locale.format(
+                "scores.entry",
+                &[
+                    ("rank", &(i + 1).to_string()),
+                    (
+                        "distance",
+                        &self
+                            .settings
+                            .units
+                            .describe_distance(locale, score.distance),
+                    ),
+                    ("time", &seconds(locale, score.time)),
+                ],
+            );
+            menu.label(
+                &(entry + assisted(locale, score.assisted)),
+                48.,
+                if i == 0 { PALETTE[14] } else { PALETTE[12] },
+            );
+        }
+        if !self.daily_history.results.is_empty() {
+            menu.label(locale.text("scores.daily"), 64., PALETTE[15]);
+        }
+        for result in self.daily_history.results.iter().take(MAX_DAILY_SHOWN) {
+            let entry = locale.format(
+                "scores.daily_entry",
+                &[
+                    ("date", &daily::date_string(result.day)),
+                    (
+                        "distance",
+                        &self.settings.units.format_distance(locale, result.distance),
+                    ),
+                ],
+            );
+            menu.label(
+                &(entry + assisted(locale, result.assisted)),
+                40.,
+                PALETTE[12],
+            );
+        }
+        if self.leaderboard.is_enabled() {
+            let heading = match self.leaderboard.board {
+                Some(Board::Seed(_)) => locale.format(
+                    "scores.online_daily",
+                    &[("date", &daily::date_string(daily::utc_day(date::now())))],
+                ),
+                _ => locale.format(
+                    "scores.online",
+                    &[("difficulty", locale.text(self.settings.difficulty.key()))],
+                ),
+            };
+            menu.label(&heading, 64., PALETTE[15]);
+            let status = match self.leaderboard.status {
+                Status::Loading => Some("scores.loading"),
+                Status::Offline => Some("scores.offline"),
+                Status::Ready if self.leaderboard.scores.is_empty() => Some("scores.empty"),
+                Status::Ready | Status::Idle => None,
+            };
+            if let Some(status) = status {
+                menu.label(locale.text(status), 40., PALETTE[12]);
+            }
+            for (i, entry) in self
+                .leaderboard
+                .scores
+                .iter()
+                .take(MAX_ONLINE_SHOWN)
+                .enumerate()
+            {
+                let entry = locale.format(
+                    "scores.online_entry",
+                    &[
+                        ("rank", &(i + 1).to_string()),
+                        ("name", &entry.name),
+                        (
+                            "distance",
+                            &self.settings.units.format_distance(locale, entry.distance),
+                        ),
+                    ],
+                );
+                menu.label(&entry, 40., PALETTE[12]);
+            }
+            let queued = self.leaderboard.queued();
+            if queued > 0 {
+                menu.label(
+                    &locale.plural("scores.queued", queued as i64, &[]),
+                    32.,
+                    PALETTE[13],
+                );
+            }
+        }
+        menu.button(MenuItem::Back, locale.text("common.back"));
+
+        if let Some(UiEvent::Activated(MenuItem::Back)) = menu.tick(&self.viewport) {
+            self.state = GameState::Title;
+        }
+    }
+
+    fn tick_achievements_menu(&mut self) {
+        let locale = &self.locale;
+        let achievements = &self.achievements;
+        let menu = &mut self.achievements_menu;
+        menu.clear();
+        menu.label(locale.text("achievements.title"), 96., PALETTE[15]);
+        menu.label(
+            &locale.format(
+                "achievements.count",
+                &[
+                    ("count", &achievements.record.unlocked.len().to_string()),
+                    ("total", &achievements.definitions.len().to_string()),
+                ],
+            ),
+            40.,
+            PALETTE[12],
+        );
+        for achievement in &achievements.definitions {
+            let unlock = achievements.unlock(&achievement.id);
+            let status = match unlock {
+                Some(unlock) => daily::date_string(daily::utc_day(unlock.time)),
+                None if achievement.condition.has_progress() => {
+                    let percent = achievements.progress(&achievement.id) * 100.;
+                    locale.format(
+                        "achievements.progress",
+                        &[("percent", &locale.number(percent.floor() as f64, 0))],
+                    )
+                }
+                None => locale.text("achievements.locked").to_string(),
+            };
+            let entry = locale.format(
+                "achievements.entry",
+                &[
+                    ("name", locale.text(&achievement.name_key())),
+                    ("description", locale.text(&achievement.description_key())),
+                    ("status", &status),
+                ],
+            );
+            let color = if unlock.is_some() {
+                PALETTE[14]
+            } else {
+                PALETTE[12]
+            };
+            menu.label(&entry, 32., color);
+        }
+        menu.button(MenuItem::Back, locale.text("common.back"));
+
+        if let Some(UiEvent::Activated(MenuItem::Back)) = menu.tick(&self.viewport) {
+            self.state = GameState::Title;
+        }
+    }
+
+    fn tick_profile_menu(&mut self) {
+        let locale = &self.locale;
+        let units = self.settings.units;
+        let profile = &self.profile;
+        let menu = &mut self.profile_menu;
+        menu.clear();
+        menu.label(locale.text("profile.title"), 96., PALETTE[15]);
+        let rows = [
+            locale.format("profile.runs", &[("count", &profile.runs.to_string())]),
+            locale.format(
+                "profile.distance",
+                &[(
+                    "distance",
+                    &units.format_distance(locale, profile.total_distance),
+                )],
+            ),
+            locale.format(
+                "profile.average",
+                &[
+                    (
+                        "distance",
+                        &units.format_distance(locale, profile.average_distance()),
+                    ),
+                    ("time", &seconds(locale, profile.average_time())),
+                ],
+            ),
+            locale.format(
+                "profile.jumps",
+                &[("count", &profile.total_jumps.to_string())],
+            ),
+            locale.format(
+                "profile.airtime",
+                &[("seconds", &locale.number(profile.longest_airtime as f64, 1))],
+            ),
+            locale.format(
+                "profile.nights",
+                &[("count", &profile.nights_survived.to_string())],
+            ),
+        ];
+        for row in &rows {
+            menu.label(row, 40., PALETTE[12]);
+        }
+        menu.label(
+            &locale.format(
+                "profile.crashes",
+                &[("count", &profile.crashes.to_string())],
+            ),
+            40.,
+            PALETTE[12],
+        );
+
+        // where runs end, the long tail beyond the last row is lumped into it
+        menu.label(locale.text("profile.deaths"), 40., PALETTE[12]);
+        let mut buckets: Vec<u32> = profile
+            .deaths
+            .iter()
+            .copied()
+            .take(PROFILE_HISTOGRAM_ROWS)
+            .collect();
+        if let Some(last) = buckets.last_mut() {
+            *last += profile
+                .deaths
+                .iter()
+                .skip(PROFILE_HISTOGRAM_ROWS)
+                .sum::<u32>();
+        }
+        let most = buckets.iter().copied().max().unwrap_or(0).max(1);
+        for (i, count) in buckets.iter().enumerate() {
+            let from = units.format_distance(locale, i as f32 * DEATH_HISTOGRAM_BUCKET);
+            let range = if i + 1 == buckets.len() && profile.deaths.len() > PROFILE_HISTOGRAM_ROWS {
+                locale.format("profile.range_open", &[("from", &from)])
+            } else {
+                let to = units.format_distance(locale, (i + 1) as f32 * DEATH_HISTOGRAM_BUCKET);
+                locale.format("profile.range", &[("from", &from), ("to", &to)])
+            };
+            let width = (*count as usize * PROFILE_HISTOGRAM_WIDTH).div_ceil(most as usize);
+            let entry = locale.format(
+                "profile.death_entry",
+                &[
+                    ("range", &range),
+                    ("bar", &"#".repeat(width)),
+                    ("count", &count.to_string()),
+                ],
+            );
+            menu.label(&entry, 32., PALETTE[12]);
+        }
+        if buckets.is_empty() {
+            menu.label(locale.text("profile.empty"), 32., PALETTE[12]);
+        }
+        if let Some(notice) = &self.notice {
+            menu.label(notice, 32., PALETTE[13]);
+        }
+        menu.button(MenuItem::ExportProfile, locale.text("profile.export"));
+        menu.button(MenuItem::Back, locale.text("common.back"));
+
+        match menu.tick(&self.viewport) {
+            Some(UiEvent::Activated(MenuItem::ExportProfile)) => self.export_profile(),
+            Some(UiEvent::Activated(MenuItem::Back)) => self.state = GameState::Title,
+            _ => {}
+        }
+    }
+
+    fn export_profile(&mut self) {
+        let notice = match self.profile.export() {
+            Ok(path) => self.locale.format("profile.exported", &[("path", &path)]),
+            Err(e) => {
+                error!("exporting the profile failed: {}", e);
+                self.locale.text("profile.export_failed").to_string()
+            }
+        };
+        self.notice = Some(notice);
+    }
+
+    fn tick_game_over_menu(&mut self) {
+        let locale = &self.locale;
+        let menu = &mut self.game_over_menu;
+        menu.clear();
+        menu.label(locale.text("game_over.title"), 96., PALETTE[15]);
+        let summary = locale.format(
+            "game_over.summary",
+            &[
+                (
+                    "distance",
+                    &self.settings.units.describe_distance(locale, self.distance),
+                ),
+                ("time", &seconds(locale, self.round_time)),
+            ],
+        );
+        menu.label(
+            &(summary + assisted(locale, self.assisted)),
+            64.,
+            PALETTE[15],
+        );
+        if let (true, Some(checkpoint)) = (self.continues_left > 0, &self.checkpoint) {
+            menu.button(
+                MenuItem::Continue,
+                &locale.plural(
+                    "game_over.continue",
+                    self.continues_left as i64,
+                    &[(
+                        "distance",
+                        &self
+                            .settings
+                            .units
+                            .format_distance(locale, checkpoint.distance),
+                    )],
+                ),
+            );
+        }
+        if self.rewind.can_rewind() {
+            menu.label(
+                &locale.format(
+                    "game_over.rewind",
+                    &[(
+                        "binding",
+                        &self.settings.bindings.describe(Action::Rewind, locale),
+                    )],
+                ),
+                48.,
+                PALETTE[13],
+            );
+        }
+        menu.button(MenuItem::Restart, locale.text("common.restart"));
+        if self.ghosts.has_best {
+            menu.button(MenuItem::ExportGhost, locale.text("game_over.export_ghost"));
+        }
+        menu.button(MenuItem::MainMenu, locale.text("game_over.main_menu"));
+        if let Some(best) = self.high_scores.best() {
+            let text = locale.format(
+                "game_over.best",
+                &[(
+                    "distance",
+                    &self.settings.units.format_distance(locale, best.distance),
+                )],
+            );
+            menu.label(&(text + assisted(locale, best.assisted)), 48., PALETTE[12]);
+        }
+        if let Some(rank) = self.leaderboard.last_rank {
+            menu.label(
+                &locale.format("game_over.online_rank", &[("rank", &rank.to_string())]),
+                48.,
+                PALETTE[14],
+            );
+        }
+        if let Some(notice) = &self.notice {
+            menu.label(notice, 32., PALETTE[13]);
+        }
+
+        match menu.tick(&self.viewport) {
+            Some(UiEvent::Activated(MenuItem::Continue)) => self.continue_from_checkpoint(),
+            Some(UiEvent::Activated(MenuItem::Restart)) => {
+                self.submit_score();
+                self.reset();
+                self.state = GameState::Running;
+            }
+            Some(UiEvent::Activated(MenuItem::ExportGhost)) => self.export_ghost(),
+            Some(UiEvent::Activated(MenuItem::MainMenu)) => {
+                self.submit_score();
+                self.new_trail();
+                self.title_menu.reset_focus();
+                self.state = GameState::Title;
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_settings(&mut self) {
+        self.audio.settings = self.settings.audio.clone();
+        self.locale.select(self.settings.language);
+        self.game_render_target
+            .texture
+            .set_filter(scale_filter(self.settings.scale_mode));
+        self.versus
+            .set_filter(scale_filter(self.settings.scale_mode));
+        if self.daily.is_none() {
+            self.obstacles
+                .configure(self.settings.difficulty.obstacle_settings());
+            if self.round_time == 0. {
+                self.obstacles.reset();
+            }
+        }
+        self.settings.save();
+    }
+
+    fn update_music(&mut self) {
+        match self.state {
+            GameState::Running
+            | GameState::Rewinding
+            | GameState::Countdown
+            | GameState::Versus
+            | GameState::Party => self.audio.play_music(),
+            GameState::Paused | GameState::Options => self.audio.pause_music(),
+            GameState::Title
+            | GameState::HighScores
+            | GameState::GameOver
+            | GameState::VersusResults
+            | GameState::PartyResults
+            | GameState::Achievements
+            | GameState::Profile => self.audio.stop_music(),
+        }
+        self.audio.update(self.music_cues(), self.delta_time);
+
+        if self.is_running() {
+            self.audio.start_ride();
+        } else {
+            self.audio.stop_ride();
+        }
+        self.audio.update_ride(&RideCues {
+            speed: self.player.speed,
+            is_moving: self.player.is_moving,
+            is_jumping: self.player.is_jumping,
+            vertical_velocity: self.player.velocity.y,
+        });
+    }
+
+    fn play_player_sounds(&mut self) {
+        for event in self.player.sound_events.drain(..) {
+            self.audio.handle(event);
+        }
+    }
+
+    fn play_race_sounds(&mut self) {
+        let versus = self
+            .versus
+            .riders
+            .iter_mut()
+            .map(|rider| &mut rider.sim.player);
+        let party = self.party.riders.iter_mut().map(|rider| &mut rider.player);
+        for player in versus.chain(party) {
+            for event in player.sound_events.drain(..) {
+                self.audio.handle(event);
+            }
+        }
     }
 
     pub fn half_size(&self) -> Vec2 {
@@ -125,77 +1224,282 @@ impl Game {
         self.state == GameState::GameOver
     }
 
+    fn is_rewinding(&self) -> bool {
+        self.state == GameState::Rewinding
+    }
+
+    fn is_race(&self) -> bool {
+        matches!(
+            self.state,
+            GameState::Versus
+                | GameState::VersusResults
+                | GameState::Party
+                | GameState::PartyResults
+        )
+    }
+
+    // a solo ride is under way, whether it's moving or not
+    fn is_riding(&self) -> bool {
+        matches!(
+            self.state,
+            GameState::Running | GameState::Paused | GameState::Countdown | GameState::Rewinding
+        )
+    }
+
     pub fn step(&mut self) {
-        if self.is_paused() || self.is_game_over() {
+        match self.state {
+            GameState::Versus => return self.step_versus(),
+            GameState::Party => return self.step_party(),
+            _ => {}
+        }
+        if !self.is_running() && !self.is_rewinding() {
+            return;
+        }
+        if self.is_rewinding() {
+            self.step_rewind();
             return;
         }
-        self.player.step(self.round_time);
-        self.obstacles.step(self.player.speed);
-        self.trees.step(self.player.speed * 0.7);
-        self.spawn_attempt();
-        self.distance += self.player.speed;
+        self.replay.record_step();
+        sim::step_ride(
+            &mut self.player,
+            &mut self.obstacles,
+            &mut self.trees,
+            &mut self.rng,
+            self.resolution,
+            self.round_time,
+            &mut self.distance,
+        );
+        self.play_player_sounds();
+        self.run_stats
+            .observe(&self.player, self.distance, self.round_time);
 
-        if self.obstacles.has_collision(&self.player) {
+        if self.obstacles.has_collision(&[&self.player]).is_some() {
             self.player.is_moving = false;
             self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME / 5.;
             self.state = GameState::GameOver;
-            stop_sound(self.jump_sound);
-            stop_sound(self.land_sound);
-            play_sound_once(self.crash_sound);
+            self.game_over_menu.reset_focus();
+            self.audio.stop_effects();
+            self.audio.play(Sfx::Crash);
+            self.audio.play_stinger();
+            self.discard_autosave();
+            self.finish_ghost();
+            self.finish_daily();
+            self.submit_online();
+            self.run_stats.crash();
+            self.achievements.track(&self.run_stats, date::now());
+            // progress towards the rest is only saved once a run is over
+            self.achievements.save();
+            self.profile
+                .record(&self.run_stats, &self.run_recorded);
+            self.run_recorded = self.run_stats;
+            self.profile.save();
+            return;
+        }
+        self.achievements.track(&self.run_stats, date::now());
+        self.ghosts.record(&self.player, self.distance);
+        self.rewind.record(self.snapshot());
+        // only once the step is known to be clear, continuing from a crash would crash again
+        if self.checkpoints_enabled && self.distance >= self.next_checkpoint {
+            self.checkpoint = Some(Snapshot::capture(self));
+            self.next_checkpoint += CHECKPOINT_INTERVAL;
+        }
+    }
+
+    fn step_versus(&mut self) {
+        let crashed = self.versus.step();
+        self.play_race_sounds();
+        if self.versus.is_over() {
+            self.finish_race(GameState::VersusResults);
+        } else if crashed {
+            self.audio.play(Sfx::Crash);
+        }
+    }
+
+    fn step_party(&mut self) {
+        let knocked_out = self.party.step();
+        self.play_race_sounds();
+        if self.party.is_over() {
+            self.finish_race(GameState::PartyResults);
+        } else if knocked_out {
+            self.audio.play(Sfx::Crash);
+        }
+    }
+
+    fn finish_race(&mut self, results: GameState) {
+        self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME / 5.;
+        self.state = results;
+        self.race_menu.reset_focus();
+        self.audio.stop_effects();
+        self.audio.play(Sfx::Crash);
+        self.audio.play_stinger();
+    }
+
+    fn step_rewind(&mut self) {
+        match self.rewind.step_back() {
+            Some(snapshot) => {
+                self.restore(&snapshot);
+                self.play_player_sounds();
+            }
+            None => self.state = GameState::Running,
+        }
+    }
+
+    // only unassisted runs count as a best to race
+    fn finish_ghost(&mut self) {
+        if self.assisted {
+            return;
+        }
+        if let Err(e) = self.ghosts.finish(self.distance) {
+            error!("saving ghost failed: {}", e);
+        }
+    }
+
+    // assisted runs don't ride the same way again from their inputs alone
+    fn submit_online(&mut self) {
+        if self.assisted {
             return;
         }
+        self.leaderboard.submit(Submission::new(
+            &self.settings.player_name,
+            self.distance,
+            self.round_time,
+            self.replay.clone(),
+        ));
     }
 
-    fn render_post_processing(&self, texture: Texture2D, is_night: bool) {
+    // the daily is ridden on normal whatever was picked in the options
+    fn run_difficulty(&self) -> Difficulty {
+        if self.daily.is_some() {
+            Difficulty::Normal
+        } else {
+            self.settings.difficulty
+        }
+    }
+
+    fn can_continue(&self) -> bool {
+        self.checkpoint.is_some() && self.continues_left > 0
+    }
+
+    fn continue_from_checkpoint(&mut self) {
+        if let Some(checkpoint) = self.checkpoint.take() {
+            checkpoint.restore(self);
+            self.checkpoint = Some(checkpoint);
+            self.rewind.clear();
+            self.continues_left -= 1;
+            self.assisted = true;
+            self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME;
+            self.state = GameState::Running;
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::capture(self)
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        snapshot.restore(self);
+    }
+
+    fn quick_save(&self) {
+        if let Err(e) = self.snapshot().save(QUICK_SAVE_SLOT) {
+            error!("quick save failed: {}", e);
+        }
+    }
+
+    fn autosave(&mut self) {
+        match self.snapshot().save(AUTOSAVE_SLOT) {
+            Ok(()) => self.has_autosave = true,
+            Err(e) => {
+                error!("autosave failed: {}", e);
+            }
+        }
+    }
+
+    fn discard_autosave(&mut self) {
+        if self.has_autosave {
+            Snapshot::remove(AUTOSAVE_SLOT);
+            self.has_autosave = false;
+        }
+    }
+
+    // the replay of the ride before it was saved is gone, so it carries on assisted
+    fn resume_autosave(&mut self) {
+        match Snapshot::load(AUTOSAVE_SLOT) {
+            Ok(snapshot) => {
+                self.restore(&snapshot);
+                self.rewind.clear();
+                self.assisted = true;
+                self.discard_autosave();
+                self.resume();
+            }
+            Err(e) => {
+                error!("resuming the ride failed: {}", e);
+                self.discard_autosave();
+            }
+        }
+    }
+
+    fn quick_load(&mut self) {
+        match Snapshot::load(QUICK_SAVE_SLOT) {
+            Ok(snapshot) => {
+                self.restore(&snapshot);
+                self.rewind.clear();
+                self.assisted = true;
+                self.state = GameState::Paused;
+            }
+            Err(e) => {
+                error!("quick load failed: {}", e);
+            }
+        }
+    }
+
+    // view is the part of the world in the texture, dest where it lands on screen
+    fn render_post_processing(
+        &self,
+        texture: Texture2D,
+        is_night: bool,
+        player: &Player,
+        view: Rect,
+        dest: Rect,
+    ) {
+        self.post_processing_material
+            .set_uniform("radius", self.settings.post_processing.vignette_radius());
+        self.post_processing_material.set_uniform(
+            "scanlines",
+            if self.settings.reduce_motion { 0. } else { 1. },
+        );
         self.post_processing_material
             .set_uniform("iTime", self.time as f32);
-        let resolution = vec2(self.resolution.x as f32, self.resolution.y as f32);
+        let origin = vec2(view.x, view.y);
+        let resolution = vec2(view.w, view.h);
         self.post_processing_material
             .set_uniform("iResolution", resolution);
 
         self.post_processing_material
             .set_uniform("darkness", if is_night { 0.8 as f32 } else { 0. as f32 });
+        self.post_processing_material
+            .set_uniform("desaturation", if self.is_rewinding() { 0.85 } else { 0. });
 
-        let mut h_pos = self.player.headlight / resolution;
+        let mut h_pos = (player.headlight - origin) / resolution;
         h_pos.y = 1. - h_pos.y;
-        let mut t_pos = self.player.taillight / resolution;
+        let mut t_pos = (player.taillight - origin) / resolution;
         t_pos.y = 1. - t_pos.y;
         t_pos.x = 1. - t_pos.x;
         self.post_processing_material
             .set_uniform("headlight", h_pos);
         self.post_processing_material
             .set_uniform("taillight", t_pos);
-        gl_use_material(self.post_processing_material);
-
-        let sw = screen_width();
-        let sh = screen_height();
-
-        let aspect: f32;
-        let width: f32;
-        let height: f32;
-        let x: f32;
-        let y: f32;
-        if sh >= sw {
-            aspect = self.resolution.y / self.resolution.x;
-            width = sw;
-            height = width * aspect;
-            x = 0.0;
-            y = (sh - height) * 0.5;
-        } else {
-            aspect = self.resolution.x / self.resolution.y;
-            height = sh;
-            width = height * aspect;
-            y = 0.0;
-            x = (sw - width) * 0.5;
+        if self.settings.post_processing != PostProcessing::Off {
+            gl_use_material(self.post_processing_material);
         }
 
         draw_texture_ex(
             texture,
-            x,
-            y,
+            dest.x,
+            dest.y,
             WHITE,
             DrawTextureParams {
-                dest_size: Some(vec2(width, height)),
+                dest_size: Some(vec2(dest.w, dest.h)),
                 flip_y: true,
                 ..Default::default()
             },
@@ -204,142 +1508,277 @@ impl Game {
     }
 
     pub fn render(&mut self, _alpha: f32) {
+        match self.state {
+            GameState::Versus | GameState::VersusResults => return self.render_versus(),
+            GameState::Party | GameState::PartyResults => return self.render_party(),
+            _ => {}
+        }
         set_camera(&self.camera);
         let is_night = self.background.render(
             self.time as f32,
             self.world_time,
             self.resolution,
+            self.viewport.game_view(),
             self.day_night_cycle_time,
             self.player.speed,
         );
+        self.is_night = is_night;
         self.trees.render(&self.tree);
+        self.markers.render(
+            self.distance,
+            self.high_scores.best().map(|best| best.distance),
+            self.player.center.x,
+            self.resolution,
+            self.settings.units,
+            &self.locale,
+        );
+        if self.state != GameState::Title {
+            self.ghosts
+                .render(&self.player, self.distance, self.round_time);
+        }
         self.player.render();
         self.obstacles.render(&self.rock);
         set_default_camera();
 
-        self.render_post_processing(self.game_render_target.texture, is_night);
-        if self.is_paused() {
-            if self.render_menu() {
-                self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME / 5.;
-                self.state = GameState::Paused;
-            } else {
-                self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME;
-                self.state = GameState::Running;
-                if !self.is_playing_music {
-                    play_sound(
-                        self.music,
-                        PlaySoundParams {
-                            looped: true,
-                            ..Default::default()
-                        },
-                    );
-                }
-            }
-        } else if self.is_game_over() {
-            let restart = self.render_game_over();
-            if restart {
-                self.reset();
-                self.state = GameState::Running;
-            }
+        self.render_post_processing(
+            self.game_render_target.texture,
+            is_night,
+            &self.player,
+            self.viewport.game_view(),
+            self.viewport.rect,
+        );
+        set_camera(&self.viewport.ui_camera());
+        match self.state {
+            GameState::Title => self.title_menu.render(),
+            GameState::Paused => self.pause_menu.render(),
+            GameState::Options => self.options.render(),
+            GameState::GameOver => self.game_over_menu.render(),
+            GameState::HighScores => self.high_scores_menu.render(),
+            GameState::Achievements => self.achievements_menu.render(),
+            GameState::Profile => self.profile_menu.render(),
+            GameState::Countdown => self.render_countdown(),
+            GameState::Running
+            | GameState::Rewinding
+            | GameState::Versus
+            | GameState::VersusResults
+            | GameState::Party
+            | GameState::PartyResults => {}
         }
-        self.render_info();
+        self.hud.render(self);
+        set_default_camera();
     }
 
-    fn render_info(&self) {
-        let mut x = 32.;
-        let y = 64.;
-        let font_size = 64.;
-        let distance_text = format!("{}m", self.distance.round() as i32);
-        let distance_text_measure = measure_text(&distance_text, None, font_size as u16, 1.);
-        draw_text(&distance_text, x, y, font_size, PALETTE[15]);
-        x += distance_text_measure.width + font_size * 0.5;
+    // each rider's lane is drawn with its own camera, then the lanes are stacked on screen
+    fn render_versus(&mut self) {
+        let mut is_night = false;
+        for rider in self.versus.riders.iter_mut() {
+            let view = rider.view();
+            let sim = &mut rider.sim;
+            set_camera(&rider.camera);
+            is_night = self.background.render(
+                self.time as f32,
+                self.world_time,
+                self.resolution,
+                view,
+                self.day_night_cycle_time,
+                sim.player.speed,
+            );
+            sim.trees.render(&self.tree);
+            self.markers.render(
+                sim.distance,
+                None,
+                sim.player.center.x,
+                self.resolution,
+                self.settings.units,
+                &self.locale,
+            );
+            sim.player.render();
+            sim.obstacles.render(&self.rock);
+        }
+        self.is_night = is_night;
+        set_default_camera();
+
+        // the lanes stay level, upright they're letterboxed rather than given more sky
+        let rect = self.viewport.fit(self.viewport.virtual_size);
+        let height = rect.h / self.versus.riders.len() as f32;
+        for (i, rider) in self.versus.riders.iter().enumerate() {
+            let dest = Rect::new(rect.x, rect.y + height * i as f32, rect.w, height);
+            self.render_post_processing(
+                rider.render_target.texture,
+                is_night,
+                &rider.sim.player,
+                rider.view(),
+                dest,
+            );
+        }
 
-        draw_text(
-            &format!("{}s", self.round_time.round() as i32),
-            x,
-            y,
-            font_size,
-            PALETTE[15],
-        );
+        set_camera(&self.viewport.ui_camera());
+        self.hud.render_versus(self);
+        if self.state == GameState::VersusResults {
+            self.race_menu.render();
+        }
+        set_default_camera();
+    }
 
-        let _speed = (self.player.speed * TPS * 3.6).round() as i32;
-    }
-
-    fn render_menu(&self) -> bool {
-        let half_width = screen_width() * 0.5;
-        let mut x;
-        let mut y = screen_height() * 0.5;
-
-        // DRAW TITLE
-        let title = "JOS HARINK'S";
-        let title_measure = measure_text(title, None, 64, 1.);
-        x = half_width - title_measure.width * 0.5;
-        y -= title_measure.height * 0.5;
-        draw_text(title, x, y, 64., PALETTE[15]);
-        y += title_measure.height * 1.8;
-
-        //DRAW SUBTITLE
-        let subtitle = "MTB MASTER";
-        let subtitle_measure = measure_text(subtitle, None, 96, 1.);
-        x = half_width - subtitle_measure.width * 0.5;
-        draw_text(subtitle, x, y, 96., PALETTE[15]);
-        y += subtitle_measure.height * 2.;
-
-        // DRAW INSTRUCTIONS
-        let press_to_start = "CLICK/TOUCH TO START";
-        let press_to_start_measure = measure_text(press_to_start, None, 64, 1.);
-        x = half_width - press_to_start_measure.width * 0.5;
-        draw_text(press_to_start, x, y, 64., PALETTE[15]);
-
-        !is_mouse_button_pressed(MouseButton::Left)
-    }
-
-    fn render_game_over(&self) -> bool {
-        let half_width = screen_width() * 0.5;
-        let mut x;
-        let mut y = screen_height() * 0.5;
-
-        // DRAW TITLE
-        let title = "GAME OVER";
-        let title_measure = measure_text(title, None, 96, 1.);
-        x = half_width - title_measure.width * 0.5;
-        y -= title_measure.height * 0.5;
-        draw_text(title, x, y, 96., PALETTE[15]);
-        y += title_measure.height * 1.4;
-
-        //DRAW SCORE
-        let score = format!(
-            "YOU BIKED {:?} METERS IN {:?} SECONDS!",
-            self.distance.round() as i32,
-            self.round_time.round() as i32
+    // everyone on the one screen, the lights follow whoever leads
+    fn render_party(&mut self) {
+        let party = &mut self.party;
+        set_camera(&self.camera);
+        let speed = party.leader().map_or(0., |leader| leader.player.speed);
+        let is_night = self.background.render(
+            self.time as f32,
+            self.world_time,
+            self.resolution,
+            self.viewport.game_view(),
+            self.day_night_cycle_time,
+            speed,
         );
-        let score_measure = measure_text(&score, None, 64, 1.);
-        x = half_width - score_measure.width * 0.5;
-        draw_text(&score, x, y, 64., PALETTE[15]);
-        y += score_measure.height * 2.;
+        self.is_night = is_night;
+        party.trees.render(&self.tree);
+        // the trail has scrolled party.distance past the front of the starting grid
+        self.markers.render(
+            party.distance,
+            None,
+            self.player.center.x,
+            self.resolution,
+            self.settings.units,
+            &self.locale,
+        );
+        for rider in party.riders.iter_mut() {
+            rider.player.render_with(&rider.palette);
+        }
+        party.obstacles.render(&self.rock);
+        set_default_camera();
 
-        // DRAW INSTRUCTIONS
-        let press_to_start = "CLICK/TOUCH TO RESTART";
-        let press_to_start_measure = measure_text(press_to_start, None, 48, 1.);
-        x = half_width - press_to_start_measure.width * 0.5;
-        draw_text(press_to_start, x, y, 48., PALETTE[15]);
+        let lights = match self.party.leader() {
+            Some(leader) => &leader.player,
+            None => &self.party.riders[0].player,
+        };
+        self.render_post_processing(
+            self.game_render_target.texture,
+            is_night,
+            lights,
+            self.viewport.game_view(),
+            self.viewport.rect,
+        );
+        set_camera(&self.viewport.ui_camera());
+        self.hud.render_party(self);
+        if self.state == GameState::PartyResults {
+            self.race_menu.render();
+        }
+        set_default_camera();
+    }
 
-        is_mouse_button_pressed(MouseButton::Left)
+    fn render_countdown(&self) {
+        let text = format!("{}", self.countdown.ceil() as i32);
+        let font_size = 192.;
+        let measure = measure_text(&text, None, font_size as u16, 1.);
+        // each number pops in large and settles as its second runs out
+        let scale = 1. + self.countdown.fract() * 0.5;
+        let area = self.viewport.game_area();
+        draw_text_ex(
+            &text,
+            area.x + area.w * 0.5 - measure.width * scale * 0.5,
+            area.y + area.h * 0.5 + measure.height * scale * 0.5,
+            TextParams {
+                font_size: font_size as u16,
+                font_scale: scale,
+                color: PALETTE[14],
+                ..Default::default()
+            },
+        );
     }
 
-    fn spawn_attempt(&mut self) {
-        self.obstacles
-            .spawn_attempt(self.resolution, self.round_time);
-        self.trees.spawn_attempt(self.resolution, self.round_time);
+    fn submit_score(&mut self) {
+        let made_the_list = self
+            .high_scores
+            .submit(Score {
+                distance: self.distance,
+                time: self.round_time,
+                assisted: self.assisted,
+            })
+            .is_some();
+        if made_the_list {
+            self.high_scores.save();
+        }
     }
 
     fn reset(&mut self) {
+        // restarting a daily counts as the attempt, the retry is practice
+        self.finish_daily();
+        self.discard_autosave();
+        self.checkpoint = None;
+        self.next_checkpoint = CHECKPOINT_INTERVAL;
+        self.continues_left = MAX_CONTINUES;
+        self.assisted = false;
+        self.rewind.reset();
+        self.run_stats = RunStats::default();
+        self.run_recorded = RunStats::default();
         self.round_time = 0.;
         self.spawn_time = 2.;
         self.player.reset();
+        self.obstacles
+            .configure(self.settings.difficulty.obstacle_settings());
         self.obstacles.reset();
         self.trees.reset();
         self.distance = 0.;
         self.day_night_cycle_time = DAY_NIGHT_CYCLE_TIME;
+        self.notice = None;
+        self.rng = Rng::new(self.seed);
+        self.world_time = 0.;
+        self.background.reseed(self.seed);
+    }
+
+    // leaves the current seed and any rival on it behind
+    fn new_trail(&mut self) {
+        self.rival = None;
+        self.seed = date::now() as u64 ^ self.rng.next_u32() as u64;
+        self.reset();
+    }
+}
+
+fn seconds(locale: &Locale, time: f32) -> String {
+    let time = time.round();
+    locale.plural("units.seconds", time as i64, &[])
+}
+
+fn assisted(locale: &Locale, assisted: bool) -> &str {
+    if assisted {
+        locale.text("common.assisted")
+    } else {
+        ""
+    }
+}
+
+// whoever went furthest, none when the best distance is shared
+fn race_winner(results: &[(Color, f32, f32)]) -> Option<usize> {
+    let best = results
+        .iter()
+        .map(|(_, distance, _)| *distance)
+        .fold(f32::MIN, f32::max);
+    let mut best_riders = results
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, distance, _))| *distance == best);
+    match (best_riders.next(), best_riders.next()) {
+        (Some((i, _)), None) => Some(i),
+        _ => None,
+    }
+}
+
+// pixel perfect scaling only stays crisp without filtering
+// renders the part of the world in view into a texture of its own, scaled onto the screen later
+fn game_camera(view: Rect, filter: FilterMode) -> (RenderTarget, Camera2D) {
+    let mut camera = Camera2D::from_display_rect(view);
+    let game_render_target = render_target(view.w as u32, view.h as u32);
+    game_render_target.texture.set_filter(filter);
+    camera.render_target = Some(game_render_target);
+    (game_render_target, camera)
+}
+
+fn scale_filter(mode: ScaleMode) -> FilterMode {
+    match mode {
+        ScaleMode::PixelPerfect => FilterMode::Nearest,
+        _ => FilterMode::Linear,
     }
 }
@@ -0,0 +1,239 @@
+use crate::{constants::*, player::Player, settings::Difficulty, storage};
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+pub const GHOST_VERSION: u32 = 2;
+const GHOST_ALPHA: f32 = 0.45;
+
+// difficulty and stamina mode change the trail and the pace as much as the seed does
+fn key(seed: u64, difficulty: Difficulty, stamina_mode: bool) -> String {
+    format!(
+        "ghost-{}-{}{}",
+        seed,
+        format!("{:?}", difficulty).to_lowercase(),
+        if stamina_mode { "-stamina" } else { "" }
+    )
+}
+
+// where the rider was after one step, stored as [distance, height, pedal, wheel] to keep files small
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "[f32; 4]", from = "[f32; 4]")]
+pub struct GhostFrame {
+    pub distance: f32,
+    pub height: f32,
+    pub pedal_theta: f32,
+    pub wheel_theta: f32,
+}
+
+impl GhostFrame {
+    pub fn capture(player: &Player, distance: f32) -> Self {
+        let state = player.state();
+        Self {
+            distance,
+            height: state.position[1],
+            pedal_theta: state.pedal_theta,
+            wheel_theta: state.wheel_theta,
+        }
+    }
+}
+
+impl From<GhostFrame> for [f32; 4] {
+    fn from(frame: GhostFrame) -> Self {
+        [
+            frame.distance,
+            frame.height,
+            frame.pedal_theta,
+            frame.wheel_theta,
+        ]
+    }
+}
+
+impl From<[f32; 4]> for GhostFrame {
+    fn from(values: [f32; 4]) -> Self {
+        Self {
+            distance: values[0],
+            height: values[1],
+            pedal_theta: values[2],
+            wheel_theta: values[3],
+        }
+    }
+}
+
+// a whole run on one seed, difficulty and stamina mode, one frame per step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ghost {
+    pub version: u32,
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub stamina_mode: bool,
+    pub distance: f32,
+    pub frames: Vec<GhostFrame>,
+}
+
+impl Ghost {
+    // the player's own best on this trail
+    pub fn load(seed: u64, difficulty: Difficulty, stamina_mode: bool) -> Option<Self> {
+        storage::load(&key(seed, difficulty, stamina_mode))
+            .and_then(|json| Self::from_json(&json).ok())
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        storage::save(&self.key(), &self.to_json())
+    }
+
+    pub fn export(&self) -> io::Result<String> {
+        storage::export(&format!("{}.json", self.key()), &self.to_json())
+    }
+
+    fn key(&self) -> String {
+        key(self.seed, self.difficulty, self.stamina_mode)
+    }
+
+    pub fn rides(&self, seed: u64, difficulty: Difficulty, stamina_mode: bool) -> bool {
+        self.seed == seed && self.difficulty == difficulty && self.stamina_mode == stamina_mode
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        let ghost: Ghost = serde_json::from_str(json)?;
+        if ghost.version != GHOST_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported ghost version {}", ghost.version),
+            ));
+        }
+        Ok(ghost)
+    }
+
+    // once the run is over the ghost waits where it crashed
+    pub fn frame_at(&self, round_time: f32) -> Option<&GhostFrame> {
+        let step = (round_time * TPS) as usize;
+        self.frames.get(step).or_else(|| self.frames.last())
+    }
+}
+
+// records the current run and plays back the one it races
+#[derive(Debug, Clone)]
+pub struct GhostRace {
+    pub ghost: Option<Ghost>,
+    // the trail being ridden
+    seed: u64,
+    difficulty: Difficulty,
+    stamina_mode: bool,
+    // whether the player has a run of their own on this seed to export
+    pub has_best: bool,
+    pub recording: Vec<GhostFrame>,
+    rider: Player,
+    palette: [Color; 16],
+}
+
+impl GhostRace {
+    pub fn new(rider: Player) -> Self {
+        // a washed out bike so it can't be mistaken for the player
+        let mut palette = *PALETTE;
+        for color in palette.iter_mut() {
+            let tint = PALETTE[13];
+            *color = Color::new(
+                (color.r + tint.r) * 0.5,
+                (color.g + tint.g) * 0.5,
+                (color.b + tint.b) * 0.5,
+                GHOST_ALPHA,
+            );
+        }
+        Self {
+            ghost: None,
+            seed: 0,
+            difficulty: Difficulty::Normal,
+            stamina_mode: false,
+            has_best: false,
+            recording: Vec::new(),
+            rider,
+            palette,
+        }
+    }
+
+    // an imported rival takes precedence over our own best on its trail
+    pub fn start(
+        &mut self,
+        seed: u64,
+        difficulty: Difficulty,
+        stamina_mode: bool,
+        rival: Option<&Ghost>,
+    ) {
+        self.seed = seed;
+        self.difficulty = difficulty;
+        self.stamina_mode = stamina_mode;
+        self.recording.clear();
+        let best = self.best();
+        self.has_best = best.is_some();
+        self.ghost = match rival {
+            Some(rival) if rival.rides(seed, difficulty, stamina_mode) => Some(rival.clone()),
+            _ => best,
+        };
+    }
+
+    // the player's own best on the trail being ridden
+    pub fn best(&self) -> Option<Ghost> {
+        Ghost::load(self.seed, self.difficulty, self.stamina_mode)
+    }
+
+    pub fn record(&mut self, player: &Player, distance: f32) {
+        self.recording.push(GhostFrame::capture(player, distance));
+    }
+
+    // rewinds and checkpoints move the run back in time, the recording follows
+    pub fn rewind_to(&mut self, round_time: f32) {
+        self.recording.truncate((round_time * TPS) as usize);
+    }
+
+    // keeps the run as the new ghost for the trail when it went further than the old one
+    pub fn finish(&mut self, distance: f32) -> io::Result<bool> {
+        if let Some(best) = self.best() {
+            if best.distance >= distance {
+                return Ok(false);
+            }
+        }
+        let ghost = Ghost {
+            version: GHOST_VERSION,
+            seed: self.seed,
+            difficulty: self.difficulty,
+            stamina_mode: self.stamina_mode,
+            distance,
+            frames: self.recording.clone(),
+        };
+        ghost.save()?;
+        self.has_best = true;
+        Ok(true)
+    }
+
+    // how far ahead of the ghost the player is, negative when behind
+    pub fn delta(&self, distance: f32, round_time: f32) -> Option<f32> {
+        let frame = self.ghost.as_ref()?.frame_at(round_time)?;
+        Some(distance - frame.distance)
+    }
+
+    pub fn render(&mut self, player: &Player, distance: f32, round_time: f32) {
+        let frame = match self.ghost.as_ref().and_then(|g| g.frame_at(round_time)) {
+            Some(frame) => *frame,
+            None => return,
+        };
+        let mut state = player.state();
+        state.position = [0., frame.height];
+        state.pedal_theta = frame.pedal_theta;
+        state.wheel_theta = frame.wheel_theta;
+        state.rear_wheel_theta = frame.wheel_theta;
+        state.is_skidding = false;
+        self.rider.restore(&state);
+        self.rider.sound_events.clear();
+        self.rider.center = player.center
+            + vec2(
+                (frame.distance - distance) * PLAYER_SPEED_TO_OBSTACLE_SPEED,
+                0.,
+            );
+        self.rider.render_with(&self.palette);
+    }
+}
@@ -0,0 +1,184 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub id: u32,
+    // the status and body, or why the server couldn't be reached
+    pub result: Result<(u16, String), String>,
+}
+
+// plain http/1.0 over a socket on a thread of its own, the game loop never waits on it
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use super::{Method, Response};
+    use std::{
+        io::{self, Read, Write},
+        net::{TcpStream, ToSocketAddrs},
+        sync::Mutex,
+        thread,
+        time::Duration,
+    };
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    static RESPONSES: Mutex<Vec<Response>> = Mutex::new(Vec::new());
+
+    pub fn send(id: u32, method: Method, url: &str, body: Option<&str>) {
+        let url = url.to_string();
+        let body = body.map(str::to_string);
+        thread::spawn(move || {
+            let result = request(method, &url, body.as_deref()).map_err(|e| e.to_string());
+            RESPONSES.lock().unwrap().push(Response { id, result });
+        });
+    }
+
+    pub fn poll() -> Vec<Response> {
+        RESPONSES.lock().unwrap().drain(..).collect()
+    }
+
+    fn invalid(message: String) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, message)
+    }
+
+    fn request(method: Method, url: &str, body: Option<&str>) -> io::Result<(u16, String)> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| invalid(format!("only http urls are supported here: {}", url)))?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, "/"),
+        };
+        let address = if authority.contains(':') {
+            authority.to_string()
+        } else {
+            format!("{}:80", authority)
+        };
+        let address = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| invalid(format!("can't resolve {}", authority)))?;
+
+        let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        // 1.0 keeps responses unchunked and closes the connection when they're done
+        let body = body.unwrap_or("");
+        write!(
+            stream,
+            "{} {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method.as_str(),
+            path,
+            authority,
+            body.len(),
+            body
+        )?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| invalid("response without a body".to_string()))?;
+        let status = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| invalid("response without a status".to_string()))?;
+        Ok((status, body.to_string()))
+    }
+}
+
+// fetch through wasm/mtb_plugin.js, finished requests queue up there until polled
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use super::{Method, Response};
+
+    extern "C" {
+        fn mtb_http_request(
+            id: u32,
+            method: *const u8,
+            method_len: u32,
+            url: *const u8,
+            url_len: u32,
+            body: *const u8,
+            body_len: u32,
+        );
+        fn mtb_http_response_id() -> i32;
+        fn mtb_http_response_status() -> u32;
+        fn mtb_http_response_len() -> u32;
+        fn mtb_http_response_take(buffer: *mut u8, buffer_len: u32);
+    }
+
+    pub fn send(id: u32, method: Method, url: &str, body: Option<&str>) {
+        let method = method.as_str();
+        let body = body.unwrap_or("");
+        unsafe {
+            mtb_http_request(
+                id,
+                method.as_ptr(),
+                method.len() as u32,
+                url.as_ptr(),
+                url.len() as u32,
+                body.as_ptr(),
+                body.len() as u32,
+            )
+        };
+    }
+
+    pub fn poll() -> Vec<Response> {
+        let mut responses = Vec::new();
+        loop {
+            let id = unsafe { mtb_http_response_id() };
+            if id < 0 {
+                return responses;
+            }
+            // a status of 0 means fetch itself failed, the body holds the reason
+            let status = unsafe { mtb_http_response_status() };
+            let mut buffer = vec![0u8; unsafe { mtb_http_response_len() } as usize];
+            unsafe { mtb_http_response_take(buffer.as_mut_ptr(), buffer.len() as u32) };
+            let body = String::from_utf8_lossy(&buffer).into_owned();
+            let result = if status == 0 {
+                Err(body)
+            } else {
+                Ok((status as u16, body))
+            };
+            responses.push(Response {
+                id: id as u32,
+                result,
+            });
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+use native as backend;
+#[cfg(target_arch = "wasm32")]
+use web as backend;
+
+// starts a request and returns its id, the response turns up in a later poll
+pub fn send(method: Method, url: &str, body: Option<&str>) -> u32 {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    backend::send(id, method, url, body);
+    id
+}
+
+// requests that finished since the last call
+pub fn poll() -> Vec<Response> {
+    backend::poll()
+}
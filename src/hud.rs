@@ -0,0 +1,375 @@
+use crate::{
+    constants::*,
+    game::{Game, GameState},
+    input::Action,
+};
+use macroquad::prelude::*;
+use std::f32::consts::PI;
+
+const FONT_SIZE: f32 = 64.;
+const MARGIN: f32 = 32.;
+const BAR_WIDTH: f32 = 320.;
+const BAR_HEIGHT: f32 = 24.;
+const ROW_HEIGHT: f32 = 56.;
+const GAUGE_RADIUS: f32 = 120.;
+const GAUGE_SEGMENTS: usize = 24;
+const GAUGE_TICKS: usize = 6;
+const NEEDLE_RESPONSE: f32 = 8.;
+// how long a race reminds each rider of their jump key
+const RACE_HINT_TIME: f32 = 3.;
+
+#[derive(Debug, Clone, Default)]
+pub struct Hud {
+    // eased towards the player's speed so the needle doesn't twitch with every stroke
+    needle: f32,
+}
+
+impl Hud {
+    pub fn tick(&mut self, speed: f32, delta_time: f32) {
+        self.needle += (speed - self.needle) * (NEEDLE_RESPONSE * delta_time).min(1.);
+    }
+
+    pub fn render(&self, game: &Game) {
+        let settings = &game.settings;
+        let locale = &game.locale;
+        let units = settings.units;
+        let area = game.viewport.safe_area();
+        let x = area.x + MARGIN;
+        let y = area.y + FONT_SIZE;
+
+        let distance_text = units.format_distance(locale, game.distance);
+        let second_text = if settings.minimal_hud {
+            units.format_speed(locale, self.needle)
+        } else {
+            format!("{}s", locale.number(game.round_time.round() as f64, 0))
+        };
+        render_texts(
+            &[&distance_text, &second_text],
+            x,
+            y,
+            settings.high_contrast,
+        );
+
+        let mut row = y + 24.;
+        if let Some(delta) = game.ghosts.delta(game.distance, game.round_time) {
+            if game.state != GameState::Title {
+                render_ghost_delta(game, delta, x, row);
+                row += ROW_HEIGHT;
+            }
+        }
+        if !settings.minimal_hud {
+            if let Some(best) = game.high_scores.best() {
+                render_best(game, best.distance, x, row);
+                row += ROW_HEIGHT;
+            }
+            self.render_speedometer(game, area);
+        }
+
+        // stamina and rewind stay in minimal mode, riding depends on them
+        if game.player.stamina_mode {
+            render_stamina(game, x, row);
+            row += ROW_HEIGHT;
+        }
+
+        render_achievement_toast(game, area);
+
+        let is_rewinding = game.state == GameState::Rewinding;
+        if is_rewinding || game.rewind.meter < REWIND_METER {
+            // upright there's no room beside the distance, so the meter goes underneath
+            if game.viewport.is_portrait() {
+                render_rewind(game, x, row);
+            } else {
+                render_rewind(game, area.right() - 352., y - 40.);
+            }
+        }
+    }
+
+    // who is who, how far they got and whether they're still riding, in the corner of each lane
+    pub fn render_versus(&self, game: &Game) {
+        let locale = &game.locale;
+        let units = game.settings.units;
+        let area = game.viewport.game_area();
+        let height = area.h / game.versus.riders.len() as f32;
+        for (i, rider) in game.versus.riders.iter().enumerate() {
+            let top = area.y + height * i as f32;
+            if i > 0 {
+                draw_line(area.x, top, area.right(), top, 8., PALETTE[0]);
+            }
+            let x = area.x + MARGIN;
+            let y = top + FONT_SIZE;
+
+            let name = locale.format("race.player", &[("number", &(i + 1).to_string())]);
+            draw_text(&name, x, y, FONT_SIZE, rider.color);
+            let offset = measure_text(&name, None, FONT_SIZE as u16, 1.).width + FONT_SIZE * 0.5;
+            let distance_text = units.format_distance(locale, rider.sim.distance);
+            let second_text = format!("{}s", locale.number(rider.sim.round_time.round() as f64, 0));
+            render_texts(
+                &[&distance_text, &second_text],
+                x + offset,
+                y,
+                game.settings.high_contrast,
+            );
+
+            let status = if rider.sim.crashed {
+                locale.text("race.crashed").to_string()
+            } else if rider.sim.round_time < RACE_HINT_TIME {
+                locale.format(
+                    "race.jump",
+                    &[("binding", &rider.bindings.describe(Action::Jump, locale))],
+                )
+            } else {
+                continue;
+            };
+            draw_text(&status, x, y + ROW_HEIGHT, 40., PALETTE[12]);
+        }
+    }
+
+    // a row per rider sharing the trail, with how far they got and whether they're out
+    pub fn render_party(&self, game: &Game) {
+        let locale = &game.locale;
+        let units = game.settings.units;
+        let area = game.viewport.safe_area();
+        let x = area.x + MARGIN;
+        for (i, rider) in game.party.riders.iter().enumerate() {
+            let y = area.y + FONT_SIZE + i as f32 * (FONT_SIZE + 16.);
+            let name = locale.format("race.player", &[("number", &(i + 1).to_string())]);
+            draw_text(&name, x, y, FONT_SIZE, rider.color);
+            let offset = measure_text(&name, None, FONT_SIZE as u16, 1.).width + FONT_SIZE * 0.5;
+
+            let distance_text = units.format_distance(locale, rider.distance);
+            let status = if rider.is_out {
+                locale.text("race.out").to_string()
+            } else if game.party.round_time < RACE_HINT_TIME {
+                locale.format(
+                    "race.jump",
+                    &[("binding", &rider.bindings.describe(Action::Jump, locale))],
+                )
+            } else {
+                String::new()
+            };
+            render_texts(
+                &[&distance_text, &status],
+                x + offset,
+                y,
+                game.settings.high_contrast,
+            );
+        }
+    }
+
+    fn render_speedometer(&self, game: &Game, area: Rect) {
+        let units = game.settings.units;
+        let readout_size = 48.;
+        let center = vec2(
+            area.right() - MARGIN - GAUGE_RADIUS,
+            area.bottom() - MARGIN - readout_size * 1.5,
+        );
+        // the dial runs over the top, from the left for standing still to the right for flat out
+        let point = |fraction: f32, radius: f32| {
+            let angle = PI * (1. - fraction);
+            center + vec2(angle.cos(), -angle.sin()) * radius
+        };
+
+        if game.settings.high_contrast {
+            draw_rectangle(
+                center.x - GAUGE_RADIUS - 16.,
+                center.y - GAUGE_RADIUS - 16.,
+                GAUGE_RADIUS * 2. + 32.,
+                GAUGE_RADIUS + readout_size * 1.5 + 32.,
+                PALETTE[0],
+            );
+        }
+
+        for i in 0..GAUGE_SEGMENTS {
+            let from = point(i as f32 / GAUGE_SEGMENTS as f32, GAUGE_RADIUS);
+            let to = point((i + 1) as f32 / GAUGE_SEGMENTS as f32, GAUGE_RADIUS);
+            draw_line(from.x, from.y, to.x, to.y, 6., PALETTE[15]);
+        }
+        for i in 0..=GAUGE_TICKS {
+            let fraction = i as f32 / GAUGE_TICKS as f32;
+            let from = point(fraction, GAUGE_RADIUS);
+            let to = point(fraction, GAUGE_RADIUS * 0.85);
+            draw_line(from.x, from.y, to.x, to.y, 4., PALETTE[12]);
+        }
+
+        let fraction = (units.speed(self.needle) / units.gauge_max()).clamp(0., 1.);
+        let tip = point(fraction, GAUGE_RADIUS * 0.8);
+        draw_line(center.x, center.y, tip.x, tip.y, 6., PALETTE[14]);
+        draw_circle(center.x, center.y, 10., PALETTE[14]);
+
+        let readout = units.format_speed(&game.locale, self.needle);
+        let measure = measure_text(&readout, None, readout_size as u16, 1.);
+        draw_text(
+            &readout,
+            center.x - measure.width * 0.5,
+            center.y + readout_size * 1.25,
+            readout_size,
+            PALETTE[15],
+        );
+    }
+}
+
+// a row of texts with a backdrop behind them in high contrast mode
+// a banner across the top for each achievement unlocked, one at a time
+fn render_achievement_toast(game: &Game, area: Rect) {
+    let toast = match game.achievements.toast() {
+        Some(toast) => toast,
+        None => return,
+    };
+    let locale = &game.locale;
+    let name = match game.achievements.definition(&toast.id) {
+        Some(achievement) => locale.text(&achievement.name_key()).to_string(),
+        None => toast.id.clone(),
+    };
+    let title = locale.text("achievements.unlocked");
+    let title_size = 32.;
+    let name_measure = measure_text(&name, None, FONT_SIZE as u16, 1.);
+    let title_measure = measure_text(title, None, title_size as u16, 1.);
+    let width = name_measure.width.max(title_measure.width) + MARGIN * 2.;
+    let height = title_size + FONT_SIZE + MARGIN;
+    // slides down in its first quarter second and back up in its last
+    let shown = (toast
+        .time_left
+        .min(ACHIEVEMENT_TOAST_TIME - toast.time_left)
+        * 4.)
+        .clamp(0., 1.);
+    let center_x = area.x + area.w * 0.5;
+    let x = center_x - width * 0.5;
+    let y = area.y + MARGIN - (1. - shown) * (height + MARGIN);
+
+    draw_rectangle(x, y, width, height, PALETTE[0]);
+    draw_rectangle_lines(x, y, width, height, 4., PALETTE[14]);
+    draw_text(
+        title,
+        center_x - title_measure.width * 0.5,
+        y + MARGIN * 0.5 + title_size,
+        title_size,
+        PALETTE[12],
+    );
+    draw_text(
+        &name,
+        center_x - name_measure.width * 0.5,
+        y + title_size + FONT_SIZE,
+        FONT_SIZE,
+        PALETTE[14],
+    );
+}
+
+fn render_texts(texts: &[&str], x: f32, y: f32, high_contrast: bool) {
+    let spacing = FONT_SIZE * 0.5;
+    let widths: Vec<f32> = texts
+        .iter()
+        .map(|text| measure_text(text, None, FONT_SIZE as u16, 1.).width)
+        .collect();
+
+    if high_contrast {
+        let width = widths.iter().sum::<f32>() + spacing * (texts.len() - 1) as f32;
+        draw_rectangle(
+            x - 16.,
+            y - FONT_SIZE,
+            width + 32.,
+            FONT_SIZE * 1.25,
+            PALETTE[0],
+        );
+    }
+
+    let mut x = x;
+    for (text, width) in texts.iter().zip(widths) {
+        draw_text(text, x, y, FONT_SIZE, PALETTE[15]);
+        x += width + spacing;
+    }
+}
+
+// how far along this ride is towards the best one, the marker is where the best one ended
+fn render_best(game: &Game, best: f32, x: f32, y: f32) {
+    let height = BAR_HEIGHT * 0.5;
+    let scale = best.max(game.distance).max(1.);
+    let fill = game.distance / scale;
+    let marker = x + BAR_WIDTH * best / scale;
+    let color = if game.distance > best {
+        PALETTE[14]
+    } else {
+        PALETTE[13]
+    };
+
+    draw_rectangle(x, y, BAR_WIDTH, height, PALETTE[0]);
+    draw_rectangle(x, y, BAR_WIDTH * fill, height, color);
+    draw_rectangle_lines(x, y, BAR_WIDTH, height, 2., PALETTE[15]);
+    draw_line(marker, y - 8., marker, y + height + 8., 4., PALETTE[14]);
+
+    let locale = &game.locale;
+    let label = format!(
+        "{} {}",
+        locale.text("hud.best"),
+        game.settings.units.format_distance(locale, best)
+    );
+    if game.settings.high_contrast {
+        let measure = measure_text(&label, None, 32, 1.);
+        draw_rectangle(
+            x + BAR_WIDTH + 8.,
+            y - 20.,
+            measure.width + 16.,
+            40.,
+            PALETTE[0],
+        );
+    }
+    draw_text(&label, x + BAR_WIDTH + 16., y + height, 32., PALETTE[12]);
+}
+
+// ahead of the ghost reads green, behind it red
+fn render_ghost_delta(game: &Game, delta: f32, x: f32, y: f32) {
+    let locale = &game.locale;
+    let sign = if delta < 0. { "-" } else { "+" };
+    let distance = game.settings.units.format_distance(locale, delta.abs());
+    let text = locale.format("hud.ghost", &[("delta", &format!("{}{}", sign, distance))]);
+    let color = if delta < 0. { PALETTE[4] } else { PALETTE[10] };
+    let font_size = 40.;
+    if game.settings.high_contrast {
+        let measure = measure_text(&text, None, font_size as u16, 1.);
+        draw_rectangle(
+            x - 16.,
+            y,
+            measure.width + 32.,
+            font_size * 1.25,
+            PALETTE[0],
+        );
+    }
+    draw_text(&text, x, y + font_size, font_size, color);
+}
+
+fn render_rewind(game: &Game, x: f32, y: f32) {
+    let fill = game.rewind.meter / REWIND_METER;
+    draw_rectangle(x, y, BAR_WIDTH, BAR_HEIGHT, PALETTE[0]);
+    draw_rectangle(
+        x + BAR_WIDTH * (1. - fill),
+        y,
+        BAR_WIDTH * fill,
+        BAR_HEIGHT,
+        PALETTE[13],
+    );
+    draw_rectangle_lines(x, y, BAR_WIDTH, BAR_HEIGHT, 4., PALETTE[15]);
+    if game.state == GameState::Rewinding {
+        draw_text(
+            game.locale.text("hud.rewind"),
+            x,
+            y + BAR_HEIGHT + 48.,
+            48.,
+            PALETTE[15],
+        );
+    }
+}
+
+fn render_stamina(game: &Game, x: f32, y: f32) {
+    let player = &game.player;
+    let fill = player.stamina / MAX_STAMINA;
+    let color = if fill < 0.25 { PALETTE[4] } else { PALETTE[10] };
+    draw_rectangle(x, y, BAR_WIDTH, BAR_HEIGHT, PALETTE[0]);
+    draw_rectangle(x, y, BAR_WIDTH * fill, BAR_HEIGHT, color);
+    draw_rectangle(
+        x,
+        y + BAR_HEIGHT,
+        BAR_WIDTH * player.cadence,
+        BAR_HEIGHT * 0.25,
+        PALETTE[14],
+    );
+    draw_rectangle_lines(x, y, BAR_WIDTH, BAR_HEIGHT, 4., PALETTE[15]);
+}
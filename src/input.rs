@@ -0,0 +1,266 @@
+use crate::locale::Locale;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+// keys that can be rebound, escape and enter stay reserved for the menus
+const BINDABLE_KEYS: [KeyCode; 49] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::LeftShift,
+    KeyCode::RightShift,
+    KeyCode::LeftControl,
+    KeyCode::RightControl,
+    KeyCode::LeftAlt,
+    KeyCode::RightAlt,
+];
+
+const BINDABLE_BUTTONS: [MouseButton; 3] =
+    [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Jump,
+    Pedal,
+    Brake,
+    StrokeLeft,
+    StrokeRight,
+    Rewind,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::Jump,
+        Action::Pedal,
+        Action::Brake,
+        Action::StrokeLeft,
+        Action::StrokeRight,
+        Action::Rewind,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Action::Jump => "action.jump",
+            Action::Pedal => "action.pedal",
+            Action::Brake => "action.brake",
+            Action::StrokeLeft => "action.stroke_left",
+            Action::StrokeRight => "action.stroke_right",
+            Action::Rewind => "action.rewind",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl Binding {
+    pub fn is_down(&self) -> bool {
+        match *self {
+            Binding::Key(key) => is_key_down(key),
+            Binding::Mouse(button) => is_mouse_button_down(button),
+        }
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        match *self {
+            Binding::Key(key) => is_key_pressed(key),
+            Binding::Mouse(button) => is_mouse_button_pressed(button),
+        }
+    }
+
+    pub fn is_released(&self) -> bool {
+        match *self {
+            Binding::Key(key) => is_key_released(key),
+            Binding::Mouse(button) => is_mouse_button_released(button),
+        }
+    }
+
+    // whatever bindable key or button went down this frame
+    pub fn pressed() -> Option<Binding> {
+        let key = BINDABLE_KEYS.iter().find(|key| is_key_pressed(**key));
+        let button = BINDABLE_BUTTONS
+            .iter()
+            .find(|button| is_mouse_button_pressed(**button));
+        key.map(|key| Binding::Key(*key))
+            .or_else(|| button.map(|button| Binding::Mouse(*button)))
+    }
+
+    // letter and digit keys have no string of their own and show their key code
+    pub fn name(&self, locale: &Locale) -> String {
+        let key = match self {
+            Binding::Key(key) => format!("binding.{:?}", key),
+            Binding::Mouse(button) => format!("binding.mouse_{:?}", button),
+        };
+        match locale.get(&key.to_lowercase()) {
+            Some(name) => name.to_string(),
+            None => match self {
+                Binding::Key(key) => format!("{:?}", key).to_uppercase(),
+                Binding::Mouse(button) => format!("MOUSE {:?}", button).to_uppercase(),
+            },
+        }
+    }
+}
+
+impl From<Binding> for String {
+    fn from(binding: Binding) -> Self {
+        match binding {
+            Binding::Key(key) => format!("key:{:?}", key),
+            Binding::Mouse(button) => format!("mouse:{:?}", button),
+        }
+    }
+}
+
+impl TryFrom<String> for Binding {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let key = value
+            .strip_prefix("key:")
+            .and_then(|name| BINDABLE_KEYS.iter().find(|k| format!("{:?}", k) == name))
+            .map(|key| Binding::Key(*key));
+        let button = value
+            .strip_prefix("mouse:")
+            .and_then(|name| BINDABLE_BUTTONS.iter().find(|b| format!("{:?}", b) == name))
+            .map(|button| Binding::Mouse(*button));
+        key.or(button)
+            .ok_or_else(|| format!("unknown binding {}", value))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    pub jump: Vec<Binding>,
+    pub pedal: Vec<Binding>,
+    pub brake: Vec<Binding>,
+    pub stroke_left: Vec<Binding>,
+    pub stroke_right: Vec<Binding>,
+    pub rewind: Vec<Binding>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            jump: vec![Binding::Mouse(MouseButton::Left)],
+            pedal: vec![Binding::Mouse(MouseButton::Right)],
+            brake: vec![Binding::Key(KeyCode::Down), Binding::Key(KeyCode::LeftShift)],
+            stroke_left: vec![Binding::Key(KeyCode::Left)],
+            stroke_right: vec![Binding::Key(KeyCode::Right)],
+            rewind: vec![Binding::Key(KeyCode::R)],
+        }
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        match action {
+            Action::Jump => &self.jump,
+            Action::Pedal => &self.pedal,
+            Action::Brake => &self.brake,
+            Action::StrokeLeft => &self.stroke_left,
+            Action::StrokeRight => &self.stroke_right,
+            Action::Rewind => &self.rewind,
+        }
+    }
+
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let bindings = match action {
+            Action::Jump => &mut self.jump,
+            Action::Pedal => &mut self.pedal,
+            Action::Brake => &mut self.brake,
+            Action::StrokeLeft => &mut self.stroke_left,
+            Action::StrokeRight => &mut self.stroke_right,
+            Action::Rewind => &mut self.rewind,
+        };
+        *bindings = vec![binding];
+    }
+
+    pub fn is_down(&self, action: Action) -> bool {
+        self.get(action).iter().any(Binding::is_down)
+    }
+
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.get(action).iter().any(Binding::is_pressed)
+    }
+
+    pub fn is_released(&self, action: Action) -> bool {
+        self.get(action).iter().any(Binding::is_released)
+    }
+
+    pub fn describe(&self, action: Action, locale: &Locale) -> String {
+        self.get(action)
+            .iter()
+            .map(|binding| binding.name(locale))
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+}
+
+// what the player asked for this frame, polled once so the player doesn't read devices itself
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlayerInput {
+    pub jump: bool,
+    pub jump_released: bool,
+    pub pedal: bool,
+    pub brake: bool,
+    pub stroke_left: bool,
+    pub stroke_right: bool,
+}
+
+impl PlayerInput {
+    pub fn poll(bindings: &Bindings) -> Self {
+        Self {
+            jump: bindings.is_down(Action::Jump),
+            jump_released: bindings.is_released(Action::Jump),
+            pedal: bindings.is_down(Action::Pedal),
+            brake: bindings.is_down(Action::Brake),
+            stroke_left: bindings.is_pressed(Action::StrokeLeft),
+            stroke_right: bindings.is_pressed(Action::StrokeRight),
+        }
+    }
+}
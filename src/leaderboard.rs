@@ -0,0 +1,278 @@
+use crate::{
+    http::{self, Method},
+    replay::Replay,
+    settings::Difficulty,
+    storage,
+};
+use macroquad::logging::{error, warn};
+use serde::{Deserialize, Serialize};
+
+// see docs/leaderboard.md for the protocol these types make up
+pub const PROTOCOL_VERSION: u32 = 1;
+pub const DEFAULT_LIMIT: usize = 10;
+const QUEUE_KEY: &str = "leaderboard-queue";
+// replays are big, localStorage isn't
+const MAX_QUEUED: usize = 10;
+const RETRY_DELAY: f64 = 2.;
+const MAX_RETRY_DELAY: f64 = 120.;
+const FETCH_ATTEMPTS: u32 = 3;
+
+// body of POST /v1/scores
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Submission {
+    pub version: u32,
+    pub name: String,
+    pub distance: f32,
+    pub time: f32,
+    pub replay: Replay,
+}
+
+impl Submission {
+    pub fn new(name: &str, distance: f32, time: f32, replay: Replay) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            name: name.to_string(),
+            distance,
+            time,
+            replay,
+        }
+    }
+}
+
+// 201 response to a submission, rank 1 is the top of the seed's board
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Accepted {
+    pub rank: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub stamina_mode: bool,
+    pub distance: f32,
+    pub time: f32,
+}
+
+// 200 response to GET /v1/scores
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopScores {
+    pub scores: Vec<Entry>,
+}
+
+// body of every 4xx and 5xx response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rejection {
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Board {
+    Seed(u64),
+    Difficulty(Difficulty),
+}
+
+impl Board {
+    pub fn query(&self, limit: usize) -> String {
+        match self {
+            Board::Seed(seed) => format!("seed={}&limit={}", seed, limit),
+            Board::Difficulty(difficulty) => format!("difficulty={:?}&limit={}", difficulty, limit),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Idle,
+    Loading,
+    Ready,
+    Offline,
+}
+
+// timeouts, rate limits and server trouble are worth another try, anything else won't change
+fn is_retryable(status: u16) -> bool {
+    status == 408 || status == 429 || status >= 500
+}
+
+fn is_success(status: u16) -> bool {
+    (200..300).contains(&status)
+}
+
+fn backoff(failures: u32) -> f64 {
+    (RETRY_DELAY * 2f64.powi(failures as i32 - 1)).min(MAX_RETRY_DELAY)
+}
+
+// submits finished runs and fetches boards without ever blocking the game,
+// runs that can't be sent wait in a queue in the save file until the server is back
+#[derive(Debug, Clone)]
+pub struct Leaderboard {
+    // none keeps every score on this device
+    pub url: Option<String>,
+    queue: Vec<Submission>,
+    sending: Option<u32>,
+    failures: u32,
+    retry_at: f64,
+    fetching: Option<u32>,
+    fetch_failures: u32,
+    fetch_at: Option<f64>,
+    pub board: Option<Board>,
+    pub scores: Vec<Entry>,
+    pub status: Status,
+    // where the latest run landed on its seed's board, once the server took it
+    pub last_rank: Option<usize>,
+}
+
+impl Leaderboard {
+    pub fn load(url: Option<String>) -> Self {
+        let queue = storage::load(QUEUE_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            url: url.map(|url| url.trim_end_matches('/').to_string()),
+            queue,
+            sending: None,
+            failures: 0,
+            retry_at: 0.,
+            fetching: None,
+            fetch_failures: 0,
+            fetch_at: None,
+            board: None,
+            scores: Vec::new(),
+            status: Status::Idle,
+            last_rank: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.url.is_some()
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn submit(&mut self, submission: Submission) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.last_rank = None;
+        self.queue.push(submission);
+        if self.queue.len() > MAX_QUEUED {
+            // the oldest run goes, unless it's the one on its way to the server
+            let oldest = if self.sending.is_some() { 1 } else { 0 };
+            self.queue.remove(oldest);
+        }
+        self.save_queue();
+    }
+
+    // a response to an earlier fetch is ignored once another board is asked for
+    pub fn fetch(&mut self, board: Board) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.board = Some(board);
+        self.scores.clear();
+        self.status = Status::Loading;
+        self.fetching = None;
+        self.fetch_failures = 0;
+        self.fetch_at = Some(0.);
+    }
+
+    pub fn tick(&mut self, now: f64) {
+        for response in http::poll() {
+            if self.sending == Some(response.id) {
+                self.sending = None;
+                self.on_submitted(response.result, now);
+            } else if self.fetching == Some(response.id) {
+                self.fetching = None;
+                self.on_fetched(response.result, now);
+            }
+        }
+
+        let url = match &self.url {
+            Some(url) => url.clone(),
+            None => return,
+        };
+        if self.sending.is_none() && now >= self.retry_at {
+            if let Some(submission) = self.queue.first() {
+                let body = serde_json::to_string(submission).unwrap();
+                let scores_url = format!("{}/v1/scores", url);
+                self.sending = Some(http::send(Method::Post, &scores_url, Some(&body)));
+            }
+        }
+        if let (None, Some(board), Some(at)) = (self.fetching, self.board, self.fetch_at) {
+            if now >= at {
+                self.fetch_at = None;
+                let board_url = format!("{}/v1/scores?{}", url, board.query(DEFAULT_LIMIT));
+                self.fetching = Some(http::send(Method::Get, &board_url, None));
+            }
+        }
+    }
+
+    fn on_submitted(&mut self, result: Result<(u16, String), String>, now: f64) {
+        match result {
+            Ok((status, body)) if is_success(status) => {
+                // an older run that was still queued doesn't speak for the latest one
+                if self.queue.len() == 1 {
+                    self.last_rank = serde_json::from_str::<Accepted>(&body)
+                        .ok()
+                        .map(|accepted| accepted.rank);
+                }
+                self.queue.remove(0);
+                self.save_queue();
+                self.failures = 0;
+            }
+            Ok((status, body)) if !is_retryable(status) => {
+                error!("leaderboard rejected a run ({}): {}", status, body);
+                self.queue.remove(0);
+                self.save_queue();
+                self.failures = 0;
+            }
+            Ok((status, _)) => self.retry_later(&format!("status {}", status), now),
+            Err(e) => self.retry_later(&e, now),
+        }
+    }
+
+    fn retry_later(&mut self, reason: &str, now: f64) {
+        self.failures += 1;
+        let delay = backoff(self.failures);
+        warn!(
+            "submitting to the leaderboard failed, retrying in {}s: {}",
+            delay, reason
+        );
+        self.retry_at = now + delay;
+    }
+
+    fn on_fetched(&mut self, result: Result<(u16, String), String>, now: f64) {
+        let retryable = match result {
+            Ok((status, body)) if is_success(status) => {
+                match serde_json::from_str::<TopScores>(&body) {
+                    Ok(top) => {
+                        self.scores = top.scores;
+                        self.status = Status::Ready;
+                        return;
+                    }
+                    Err(e) => {
+                        error!("reading the leaderboard failed: {}", e);
+                        false
+                    }
+                }
+            }
+            Ok((status, _)) => is_retryable(status),
+            Err(_) => true,
+        };
+        self.fetch_failures += 1;
+        if retryable && self.fetch_failures < FETCH_ATTEMPTS {
+            self.fetch_at = Some(now + backoff(self.fetch_failures));
+        } else {
+            self.status = Status::Offline;
+        }
+    }
+
+    fn save_queue(&self) {
+        if let Err(e) = storage::save(QUEUE_KEY, &serde_json::to_string(&self.queue).unwrap()) {
+            error!("saving the leaderboard queue failed: {}", e);
+        }
+    }
+}
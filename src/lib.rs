@@ -0,0 +1,45 @@
+#![allow(dead_code)]
+#![feature(drain_filter)]
+#![feature(exclusive_range_pattern)]
+pub mod achievements;
+pub mod audio;
+pub mod background;
+pub mod constants;
+pub mod daily;
+pub mod foley;
+pub mod game;
+pub mod geometry;
+pub mod ghost;
+pub mod http;
+pub mod hud;
+pub mod input;
+pub mod leaderboard;
+pub mod lifecycle;
+pub mod locale;
+pub mod markers;
+pub mod music;
+pub mod obstacles;
+pub mod options;
+pub mod particles;
+pub mod party;
+pub mod player;
+pub mod profile;
+pub mod replay;
+pub mod rewind;
+pub mod rng;
+pub mod scores;
+pub mod settings;
+pub mod shaders;
+pub mod sim;
+pub mod snapshot;
+pub mod storage;
+pub mod synth;
+pub mod ui;
+pub mod units;
+pub mod util;
+pub mod verify;
+pub mod versus;
+pub mod viewport;
+
+#[macro_use]
+extern crate lazy_static;
@@ -0,0 +1,62 @@
+use crate::constants::*;
+
+// macroquad 0.3 passes neither focus nor minimize on from miniquad, so on desktop the stalled
+// frame a minimized or covered window comes back with is the only hint
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    pub fn take_interrupted() -> bool {
+        false
+    }
+
+    pub fn is_active() -> bool {
+        true
+    }
+}
+
+// visibility and focus tracked by wasm/mtb_plugin.js
+#[cfg(target_arch = "wasm32")]
+mod web {
+    extern "C" {
+        fn mtb_take_interrupted() -> u32;
+        fn mtb_page_active() -> u32;
+    }
+
+    pub fn take_interrupted() -> bool {
+        unsafe { mtb_take_interrupted() != 0 }
+    }
+
+    pub fn is_active() -> bool {
+        unsafe { mtb_page_active() != 0 }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+use native as backend;
+#[cfg(target_arch = "wasm32")]
+use web as backend;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    Suspended,
+    Resumed,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Lifecycle {
+    is_suspended: bool,
+}
+
+impl Lifecycle {
+    pub fn poll(&mut self, delta_time: f32) -> Option<LifecycleEvent> {
+        let interrupted = backend::take_interrupted() || delta_time > SUSPEND_FRAME_TIME;
+        if interrupted && !self.is_suspended {
+            self.is_suspended = true;
+            return Some(LifecycleEvent::Suspended);
+        }
+        if self.is_suspended && backend::is_active() {
+            self.is_suspended = false;
+            return Some(LifecycleEvent::Resumed);
+        }
+        None
+    }
+}
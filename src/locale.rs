@@ -0,0 +1,322 @@
+use macroquad::file::load_string;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::env;
+
+    // posix locale variables, most specific first, e.g. "nl_NL.UTF-8" or "nl:en"
+    pub fn preferred_languages() -> Vec<String> {
+        ["LANGUAGE", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .flat_map(|value| value.split(':').map(str::to_string).collect::<Vec<_>>())
+            .collect()
+    }
+}
+
+// navigator.languages handed over by wasm/mtb_plugin.js as a comma separated list
+#[cfg(target_arch = "wasm32")]
+mod web {
+    extern "C" {
+        fn mtb_preferred_languages(buffer: *mut u8, buffer_len: u32) -> u32;
+    }
+
+    pub fn preferred_languages() -> Vec<String> {
+        let mut buffer = vec![0u8; 256];
+        let len = unsafe { mtb_preferred_languages(buffer.as_mut_ptr(), buffer.len() as u32) };
+        buffer.truncate(len as usize);
+        String::from_utf8(buffer)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+use native as backend;
+#[cfg(target_arch = "wasm32")]
+use web as backend;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    English,
+    Dutch,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Dutch];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Dutch => "nl",
+        }
+    }
+
+    // always in the language itself, so it can be found again from a language you can't read
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "ENGLISH",
+            Language::Dutch => "NEDERLANDS",
+        }
+    }
+
+    // takes tags like "nl", "nl-BE" or "nl_NL.UTF-8"
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let code = tag.trim().split(&['-', '_', '.'][..]).next()?;
+        Language::ALL
+            .iter()
+            .copied()
+            .find(|language| language.code().eq_ignore_ascii_case(code))
+    }
+
+    // the first language the system or browser prefers that we have strings for
+    pub fn detect() -> Self {
+        backend::preferred_languages()
+            .iter()
+            .find_map(|tag| Language::from_tag(tag))
+            .unwrap_or(Language::English)
+    }
+
+    // (thousands, decimal)
+    pub fn separators(&self) -> (char, char) {
+        match self {
+            Language::English => (',', '.'),
+            Language::Dutch => ('.', ','),
+        }
+    }
+
+    // english and dutch only tell one apart from the rest, more categories come with more languages
+    pub fn plural_category(&self, count: i64) -> &'static str {
+        match self {
+            Language::English | Language::Dutch if count == 1 => "one",
+            _ => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Text(String),
+    Plural(HashMap<String, String>),
+}
+
+// lang/<code>.json, flat keys to either a string or an object of plural categories
+type StringTable = HashMap<String, Entry>;
+
+// the built-in font only covers ascii, so the tables stay within it
+#[derive(Debug, Clone)]
+pub struct Locale {
+    pub language: Language,
+    pub detected: Language,
+    tables: Vec<(Language, StringTable)>,
+}
+
+impl Locale {
+    // loads every language up front so switching in the options doesn't have to wait on a file
+    pub async fn load() -> Self {
+        let mut tables = Vec::with_capacity(Language::ALL.len());
+        for language in Language::ALL {
+            let path = format!("lang/{}.json", language.code());
+            let table = match load_string(&path).await {
+                Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                    macroquad::logging::error!("parsing {} failed: {}", path, e);
+                    StringTable::new()
+                }),
+                Err(e) => {
+                    macroquad::logging::error!("loading {} failed: {}", path, e);
+                    StringTable::new()
+                }
+            };
+            tables.push((language, table));
+        }
+        let detected = Language::detect();
+        Self {
+            language: detected,
+            detected,
+            tables,
+        }
+    }
+
+    // none follows the system or browser language
+    pub fn select(&mut self, language: Option<Language>) {
+        self.language = language.unwrap_or(self.detected);
+    }
+
+    fn entry(&self, language: Language, key: &str) -> Option<&Entry> {
+        self.tables
+            .iter()
+            .find(|(l, _)| *l == language)
+            .and_then(|(_, table)| table.get(key))
+    }
+
+    // missing strings fall back to english, so a half finished translation is still playable
+    fn lookup(&self, key: &str) -> Option<&Entry> {
+        self.entry(self.language, key)
+            .or_else(|| self.entry(Language::English, key))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        match self.lookup(key)? {
+            Entry::Text(text) => Some(text),
+            Entry::Plural(forms) => forms.get("other").map(String::as_str),
+        }
+    }
+
+    // the key itself shows up on screen when nobody has a string for it
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.get(key).unwrap_or(key)
+    }
+
+    // fills in {name} placeholders
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        fill(self.text(key), args)
+    }
+
+    // picks the form for count and fills in {count} along with the other placeholders
+    pub fn plural(&self, key: &str, count: i64, args: &[(&str, &str)]) -> String {
+        let category = self.language.plural_category(count);
+        let template = match self.lookup(key) {
+            Some(Entry::Plural(forms)) => forms
+                .get(category)
+                .or_else(|| forms.get("other"))
+                .map(String::as_str)
+                .unwrap_or(key),
+            Some(Entry::Text(text)) => text,
+            None => key,
+        };
+        let count = self.number(count as f64, 0);
+        fill(template, &[&[("count", count.as_str())], args].concat())
+    }
+
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        let (thousands, decimal) = self.language.separators();
+        let text = format!("{:.*}", decimals, value.abs());
+        let (whole, fraction) = match text.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (text.as_str(), None),
+        };
+
+        let mut result = String::new();
+        if value < 0. && text.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            result.push('-');
+        }
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                result.push(thousands);
+            }
+            result.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            result.push(decimal);
+            result.push_str(fraction);
+        }
+        result
+    }
+}
+
+fn fill(template: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the shipped string tables, without going through the asset loader
+    fn locale(language: Language) -> Locale {
+        let table = |json: &str| serde_json::from_str::<StringTable>(json).unwrap();
+        Locale {
+            language,
+            detected: language,
+            tables: vec![
+                (
+                    Language::English,
+                    table(include_str!("../assets/lang/en.json")),
+                ),
+                (
+                    Language::Dutch,
+                    table(include_str!("../assets/lang/nl.json")),
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn plurals_in_english() {
+        let locale = locale(Language::English);
+        assert_eq!(locale.plural("units.seconds", 0, &[]), "0 SECONDS");
+        assert_eq!(locale.plural("units.seconds", 1, &[]), "1 SECOND");
+        assert_eq!(locale.plural("units.seconds", 2, &[]), "2 SECONDS");
+        assert_eq!(locale.plural("units.seconds", 1500, &[]), "1,500 SECONDS");
+    }
+
+    #[test]
+    fn plurals_in_dutch() {
+        let locale = locale(Language::Dutch);
+        assert_eq!(locale.plural("units.seconds", 0, &[]), "0 SECONDEN");
+        assert_eq!(locale.plural("units.seconds", 1, &[]), "1 SECONDE");
+        assert_eq!(locale.plural("units.seconds", 2, &[]), "2 SECONDEN");
+        assert_eq!(locale.plural("units.seconds", 1500, &[]), "1.500 SECONDEN");
+    }
+
+    #[test]
+    fn numbers_in_english() {
+        let locale = locale(Language::English);
+        assert_eq!(locale.number(0., 0), "0");
+        assert_eq!(locale.number(999., 0), "999");
+        assert_eq!(locale.number(1000., 0), "1,000");
+        assert_eq!(locale.number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(locale.number(0.5, 1), "0.5");
+        assert_eq!(locale.number(-1250., 0), "-1,250");
+        // nothing left to be negative about once it's rounded
+        assert_eq!(locale.number(-0.01, 1), "0.0");
+    }
+
+    #[test]
+    fn numbers_in_dutch() {
+        let locale = locale(Language::Dutch);
+        assert_eq!(locale.number(0., 0), "0");
+        assert_eq!(locale.number(999., 0), "999");
+        assert_eq!(locale.number(1000., 0), "1.000");
+        assert_eq!(locale.number(1234567.891, 2), "1.234.567,89");
+        assert_eq!(locale.number(0.5, 1), "0,5");
+        assert_eq!(locale.number(-1250., 0), "-1.250");
+    }
+
+    #[test]
+    fn missing_strings_fall_back_to_english() {
+        let mut locale = locale(Language::Dutch);
+        locale.tables[1].1.remove("title.start");
+        assert_eq!(locale.text("title.start"), "START");
+        assert_eq!(locale.text("no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn placeholders_are_filled_in() {
+        let locale = locale(Language::English);
+        assert_eq!(
+            locale.format(
+                "scores.entry",
+                &[("rank", "1"), ("distance", "10m"), ("time", "5s")]
+            ),
+            "1. 10m IN 5s"
+        );
+    }
+
+    #[test]
+    fn language_tags_are_recognized() {
+        assert_eq!(Language::from_tag("nl"), Some(Language::Dutch));
+        assert_eq!(Language::from_tag("nl-BE"), Some(Language::Dutch));
+        assert_eq!(Language::from_tag("nl_NL.UTF-8"), Some(Language::Dutch));
+        assert_eq!(Language::from_tag("EN-us"), Some(Language::English));
+        assert_eq!(Language::from_tag("de"), None);
+    }
+}
@@ -1,33 +1,98 @@
-#![allow(dead_code)]
-#![feature(drain_filter)]
-#![feature(exclusive_range_pattern)]
-mod background;
-mod constants;
-mod game;
-mod geometry;
-mod obstacles;
-mod player;
-mod shaders;
-mod util;
-
-#[macro_use]
-extern crate lazy_static;
-use constants::*;
-use macroquad::{prelude::*, window, audio::load_sound};
-
-use game::*;
+use macroquad::{
+    audio::{load_sound, load_sound_from_bytes},
+    prelude::*,
+    window, Window,
+};
+use mtb::{
+    audio::Sounds,
+    constants::*,
+    foley::Foley,
+    game::*,
+    leaderboard::Submission,
+    lifecycle::{Lifecycle, LifecycleEvent},
+    locale::Locale,
+    music::{self, Layer},
+    settings::{DisplayMode, Settings},
+    verify::verify,
+};
+use std::{env, fs, process};
 
 fn window_conf() -> window::Conf {
+    let settings = Settings::load();
     window::Conf {
         window_title: "Jos Harink's MTB Master".to_owned(),
-        high_dpi: true,
-        fullscreen: true,
+        high_dpi: settings.high_dpi,
+        fullscreen: settings.display_mode == DisplayMode::Fullscreen,
         ..Default::default()
     }
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
+// `mtb verify <file>` checks a submitted run, or each run in a list of them like leaderboard-queue.json
+fn command(args: &[String]) -> Option<i32> {
+    match args.first().map(String::as_str) {
+        Some("verify") => Some(match args.get(1) {
+            Some(path) => verify_file(path),
+            None => {
+                eprintln!("usage: mtb verify <submission.json>");
+                2
+            }
+        }),
+        _ => None,
+    }
+}
+
+fn verify_file(path: &str) -> i32 {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) => {
+            eprintln!("can't read {}: {}", path, e);
+            return 2;
+        }
+    };
+    let submissions = match serde_json::from_str::<Vec<Submission>>(&json) {
+        Ok(submissions) => submissions,
+        Err(_) => match serde_json::from_str::<Submission>(&json) {
+            Ok(submission) => vec![submission],
+            Err(e) => {
+                eprintln!("{} isn't a submission: {}", path, e);
+                return 2;
+            }
+        },
+    };
+
+    let mut rejected = 0;
+    for submission in &submissions {
+        let replay = &submission.replay;
+        match verify(submission.distance, submission.time, replay) {
+            Ok(outcome) => println!(
+                "ok: {} rode {}m in {}s on seed {}",
+                submission.name, outcome.distance, outcome.time, replay.seed
+            ),
+            Err(e) => {
+                rejected += 1;
+                println!(
+                    "rejected: {} on seed {}: {}",
+                    submission.name, replay.seed, e
+                );
+            }
+        }
+    }
+    if rejected == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+fn main() {
+    // subcommands do their work without opening a window
+    if let Some(code) = command(&env::args().skip(1).collect::<Vec<_>>()) {
+        process::exit(code);
+    }
+    Window::from_config(window_conf(), run());
+}
+
+async fn run() {
     set_pc_assets_folder("assets");
     let mut accumulator: f32 = 0.;
     let rock = load_texture("rock.png").await.unwrap();
@@ -36,26 +101,38 @@ async fn main() {
     let tree = load_texture("tree.png").await.unwrap();
     tree.set_filter(FilterMode::Nearest);
     
-    let crash = load_sound("crash.wav").await.unwrap();
-    let jump = load_sound("jump.wav").await.unwrap();
-    let land = load_sound("land.wav").await.unwrap();
-    let music = load_sound("greensleeves.wav").await.unwrap();
+    let base_track = load_file(music::BASE_TRACK).await.unwrap();
+    let mut music_layers = vec![(Layer::Base, load_sound_from_bytes(&base_track).await.unwrap())];
+    music_layers.extend(music::synthesize_stems(music::stem_seconds(&base_track)).await);
+
+    let sounds = Sounds {
+        crash: load_sound("crash.wav").await.unwrap(),
+        jump: load_sound("jump.wav").await.unwrap(),
+        land: load_sound("land.wav").await.unwrap(),
+        skid: load_sound("skid.wav").await.unwrap(),
+        music_layers,
+        stinger: music::synthesize_stinger().await,
+        foley: Foley::load().await,
+    };
     
-    let mut state = Game::new(rock, tree, crash, jump, land, music);
+    let locale = Locale::load().await;
+    let mut state = Game::new(rock, tree, sounds, Settings::load(), locale);
+    let mut lifecycle = Lifecycle::default();
     loop {
         if is_key_released(KeyCode::Escape) {
-            state.state = match state.state {
-                GameState::Paused => GameState::Running,
-                GameState::Running => GameState::Paused,
-                GameState::GameOver => GameState::GameOver,
-            };
+            state.back();
         }
 
         let delta_time = get_frame_time();
+        match lifecycle.poll(delta_time) {
+            Some(LifecycleEvent::Suspended) => state.suspend(),
+            Some(LifecycleEvent::Resumed) => state.wake(),
+            None => {}
+        }
+
         clear_background(PALETTE[0]);
-        if delta_time > 1. {
-            // skip updating and make sure the pause menu is shown
-            state.state = GameState::Paused;
+        if delta_time > SUSPEND_FRAME_TIME {
+            // skip updating, the stalled frame would otherwise be simulated in one go
             next_frame().await;
             continue;
         }
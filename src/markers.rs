@@ -0,0 +1,113 @@
+use crate::{constants::*, locale::Locale, storage, units::Units};
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const FRIENDS_KEY: &str = "friends";
+const SIGNPOST_HEIGHT: f32 = 160.;
+const POLE_HEIGHT: f32 = 260.;
+const FLAG_WIDTH: f32 = 96.;
+const FLAG_HEIGHT: f32 = 56.;
+// how far off screen a marker can start and still have its label poke in
+const CULL_MARGIN: f32 = 320.;
+
+// someone else's best, shared by dropping a friends.json next to the settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Flag {
+    pub name: String,
+    pub distance: f32,
+}
+
+// signposts and flags along the trail, placed by distance rather than kept in the world
+#[derive(Debug, Clone, Default)]
+pub struct TrailMarkers {
+    pub friends: Vec<Flag>,
+}
+
+impl TrailMarkers {
+    pub fn load() -> Self {
+        let friends = storage::load(FRIENDS_KEY)
+            .and_then(|json| match serde_json::from_str(&json) {
+                Ok(friends) => Some(friends),
+                Err(e) => {
+                    error!("reading friends failed: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        Self { friends }
+    }
+
+    // player_x is where the rider is drawn, the current distance sits right under it
+    pub fn render(
+        &self,
+        distance: f32,
+        personal_best: Option<f32>,
+        player_x: f32,
+        resolution: Vec2,
+        units: Units,
+        locale: &Locale,
+    ) {
+        let to_screen = |at: f32| player_x + (at - distance) * PLAYER_SPEED_TO_OBSTACLE_SPEED;
+        let visible = |x: f32| x > -CULL_MARGIN && x < resolution.x + CULL_MARGIN;
+        let ground = resolution.y;
+
+        let interval = units.signpost_interval();
+        let left_edge = distance - (player_x + CULL_MARGIN) / PLAYER_SPEED_TO_OBSTACLE_SPEED;
+        let mut at = (left_edge / interval).ceil().max(1.) * interval;
+        while visible(to_screen(at)) {
+            render_signpost(to_screen(at), ground, &units.format_distance(locale, at));
+            at += interval;
+        }
+
+        for friend in &self.friends {
+            let x = to_screen(friend.distance);
+            if visible(x) {
+                let mut color = PALETTE[13];
+                color.a = 0.5;
+                render_flag(x, ground, &friend.name, color);
+            }
+        }
+
+        if let Some(best) = personal_best {
+            let x = to_screen(best);
+            if visible(x) {
+                render_flag(x, ground, locale.text("markers.personal_best"), PALETTE[14]);
+            }
+        }
+    }
+}
+
+fn render_signpost(x: f32, ground: f32, text: &str) {
+    let font_size = 40.;
+    let measure = measure_text(text, None, font_size as u16, 1.);
+    let board = Rect::new(
+        x - measure.width * 0.5 - 12.,
+        ground - SIGNPOST_HEIGHT,
+        measure.width + 24.,
+        font_size + 8.,
+    );
+    draw_rectangle(x - 4., board.y, 8., SIGNPOST_HEIGHT, PALETTE[3]);
+    draw_rectangle(board.x, board.y, board.w, board.h, PALETTE[6]);
+    draw_rectangle_lines(board.x, board.y, board.w, board.h, 4., PALETTE[3]);
+    draw_text(
+        text,
+        board.x + 12.,
+        board.y + font_size * 0.85,
+        font_size,
+        PALETTE[15],
+    );
+}
+
+fn render_flag(x: f32, ground: f32, label: &str, color: Color) {
+    let top = ground - POLE_HEIGHT;
+    let mut pole = PALETTE[12];
+    pole.a = color.a;
+    draw_rectangle(x - 3., top, 6., POLE_HEIGHT, pole);
+    draw_triangle(
+        vec2(x + 3., top),
+        vec2(x + 3. + FLAG_WIDTH, top + FLAG_HEIGHT * 0.5),
+        vec2(x + 3., top + FLAG_HEIGHT),
+        color,
+    );
+    draw_text(label, x + 12., top - 12., 40., color);
+}
@@ -0,0 +1,184 @@
+use crate::{constants::*, synth};
+use macroquad::audio::{
+    load_sound_from_bytes, play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound,
+};
+
+pub const BASE_TRACK: &str = "greensleeves.wav";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Base,
+    Speed,
+    Night,
+    Danger,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MusicCues {
+    pub speed: f32,
+    pub is_night: bool,
+    pub danger: f32,
+    pub is_paused: bool,
+}
+
+impl Layer {
+    pub fn target_volume(&self, cues: &MusicCues) -> f32 {
+        let volume = match self {
+            Layer::Base => {
+                let night = if cues.is_night { 0.6 } else { 1. };
+                night * (1. - cues.danger * 0.3)
+            }
+            // the hurried take of the drums takes over as an obstacle comes up
+            Layer::Speed => {
+                let speed = ((cues.speed - DEFAULT_PLAYER_SPEED)
+                    / (MUSIC_FULL_SPEED - DEFAULT_PLAYER_SPEED))
+                    .clamp(0., 1.);
+                speed * (1. - cues.danger)
+            }
+            Layer::Night => {
+                if cues.is_night {
+                    1.
+                } else {
+                    0.
+                }
+            }
+            Layer::Danger => cues.danger,
+        };
+        if cues.is_paused {
+            volume * MUSIC_PAUSED_DUCK
+        } else {
+            volume
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Stem {
+    layer: Layer,
+    sound: Sound,
+    volume: f32,
+}
+
+async fn load(samples: &[f32]) -> Sound {
+    load_sound_from_bytes(&synth::to_wav(samples))
+        .await
+        .unwrap()
+}
+
+// the stems loop with the base track, so they last as long as it does. a base track that
+// can't be measured gets the stems' own few bars
+pub fn stem_seconds(base_track: &[u8]) -> f32 {
+    synth::wav_seconds(base_track).unwrap_or(MUSIC_STEM_BARS as f32 * 4. * 60. / MUSIC_TEMPO)
+}
+
+// as many whole bars as come closest to the tempo in that time
+pub fn stem_bars(seconds: f32) -> usize {
+    ((seconds * MUSIC_TEMPO / (4. * 60.)).round() as usize).max(1)
+}
+
+// everything but the base track is synthesized at startup like the foley. macroquad has no
+// filters or playback rate, so the danger stem is the speed drums rendered at double tempo and
+// a higher pitch, and the mix swaps between them by volume
+pub async fn synthesize_stems(seconds: f32) -> Vec<(Layer, Sound)> {
+    let bars = stem_bars(seconds);
+    vec![
+        (
+            Layer::Speed,
+            load(&synth::drums(bars, seconds, 1., 3)).await,
+        ),
+        (Layer::Night, load(&synth::pad(seconds)).await),
+        (
+            Layer::Danger,
+            load(&synth::drums(bars * 2, seconds, 1.5, 5)).await,
+        ),
+    ]
+}
+
+pub async fn synthesize_stinger() -> Sound {
+    load(&synth::stinger(17)).await
+}
+
+#[derive(Debug, Clone)]
+pub struct AdaptiveMusic {
+    stems: Vec<Stem>,
+    stinger: Sound,
+    is_playing: bool,
+}
+
+impl AdaptiveMusic {
+    pub fn new(layers: Vec<(Layer, Sound)>, stinger: Sound) -> Self {
+        Self {
+            stems: layers
+                .into_iter()
+                .map(|(layer, sound)| Stem {
+                    layer,
+                    sound,
+                    volume: 0.,
+                })
+                .collect(),
+            stinger,
+            is_playing: false,
+        }
+    }
+
+    // all stems start together so they stay in sync, silent ones just sit at zero
+    pub fn start(&mut self, cues: &MusicCues, channel_volume: f32) {
+        for stem in &mut self.stems {
+            stem.volume = stem.layer.target_volume(cues);
+            play_sound(
+                stem.sound,
+                PlaySoundParams {
+                    looped: true,
+                    volume: stem.volume * channel_volume,
+                },
+            );
+        }
+        self.is_playing = true;
+    }
+
+    pub fn stop(&mut self) {
+        for stem in &mut self.stems {
+            stop_sound(stem.sound);
+            stem.volume = 0.;
+        }
+        self.is_playing = false;
+    }
+
+    pub fn update(&mut self, cues: &MusicCues, channel_volume: f32, delta_time: f32) {
+        if !self.is_playing {
+            return;
+        }
+        let max_change = MUSIC_FADE_SPEED * delta_time;
+        for stem in &mut self.stems {
+            let target = stem.layer.target_volume(cues);
+            stem.volume += (target - stem.volume).clamp(-max_change, max_change);
+            set_sound_volume(stem.sound, stem.volume * channel_volume);
+        }
+    }
+
+    pub fn play_stinger(&self, channel_volume: f32) {
+        play_sound(
+            self.stinger,
+            PlaySoundParams {
+                looped: false,
+                volume: channel_volume,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stems_fit_whole_bars_into_the_base_track() {
+        // a bar lasts two seconds at 120 bpm
+        assert_eq!(stem_bars(8.), 4);
+        assert_eq!(stem_bars(91.), 46);
+        assert_eq!(stem_bars(90.9), 45);
+        assert_eq!(stem_bars(0.5), 1);
+        assert_eq!(stem_seconds(&synth::to_wav(&synth::pad(30.))), 30.);
+        assert_eq!(stem_seconds(b"not a wav"), 8.);
+    }
+}
@@ -1,5 +1,6 @@
-use crate::{constants::*, geometry::Rectangle, player::Player};
-use macroquad::{prelude::*, rand::gen_range};
+use crate::{constants::*, geometry::Rectangle, player::Player, rng::Rng};
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 struct Obstacle {
@@ -52,6 +53,22 @@ pub struct ObstaclePool {
     settings: ObstaclePoolSettings,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObstacleState {
+    pub size: [f32; 2],
+    pub position: [f32; 2],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObstaclePoolState {
+    pub obstacles: Vec<ObstacleState>,
+    pub base_chance: f32,
+    pub spawn_chance: f32,
+    pub spawn_interval: f32,
+    pub last_spawn: f32,
+    pub obstacle_size: [f32; 2],
+}
+
 #[derive(Clone)]
 pub struct ObstaclePoolSettings {
     pub base_spawn_chance: f32,
@@ -71,6 +88,18 @@ impl Default for ObstaclePoolSettings {
     }
 }
 
+impl ObstaclePoolSettings {
+    // the trees behind the trail come from a pool too, they just never collide
+    pub fn trees() -> Self {
+        Self {
+            base_spawn_chance: 0.8,
+            spawn_interval: 10.,
+            base_size: vec2(343., 500.),
+            max_size: vec2(343., 500.),
+        }
+    }
+}
+
 impl ObstaclePool {
     pub fn new(capacity: usize, settings: Option<ObstaclePoolSettings>) -> ObstaclePool {
         let settings = match settings {
@@ -90,20 +119,20 @@ impl ObstaclePool {
         }
     }
 
-    pub fn spawn_attempt(&mut self, resolution: Vec2, round_time: f32) {
+    pub fn spawn_attempt(&mut self, resolution: Vec2, round_time: f32, rng: &mut Rng) {
         let dt = round_time - self.last_spawn;
         if dt < self.spawn_interval {
             return;
         }
 
-        let roll = gen_range(0f32, 1f32);
+        let roll = rng.gen_f32(0., 1.);
         if roll <= self.spawn_chance {
             self.last_spawn = round_time;
             self.base_chance += 0.01;
             self.base_chance = self.base_chance.min(0.9);
             self.spawn_chance = self.base_chance;
 
-            let percent: i32 = gen_range(0, 100);
+            let percent = rng.gen_i32(0, 100);
             self.obstacle_size += match percent {
                 0..33 => vec2(0., 1.),
                 33..66 => vec2(1., 0.),
@@ -118,6 +147,43 @@ impl ObstaclePool {
         }
     }
 
+    pub fn state(&self) -> ObstaclePoolState {
+        ObstaclePoolState {
+            obstacles: self
+                .obstacles
+                .iter()
+                .map(|o| ObstacleState {
+                    size: o.size.into(),
+                    position: o.position.into(),
+                })
+                .collect(),
+            base_chance: self.base_chance,
+            spawn_chance: self.spawn_chance,
+            spawn_interval: self.spawn_interval,
+            last_spawn: self.last_spawn,
+            obstacle_size: self.obstacle_size.into(),
+        }
+    }
+
+    pub fn restore(&mut self, state: &ObstaclePoolState) {
+        self.obstacles.clear();
+        self.obstacles
+            .extend(state.obstacles.iter().map(|o| Obstacle {
+                size: o.size.into(),
+                position: o.position.into(),
+            }));
+        self.base_chance = state.base_chance;
+        self.spawn_chance = state.spawn_chance;
+        self.spawn_interval = state.spawn_interval;
+        self.last_spawn = state.last_spawn;
+        self.obstacle_size = state.obstacle_size.into();
+    }
+
+    // takes effect on the next reset so a running round keeps its spawn rate
+    pub fn configure(&mut self, settings: ObstaclePoolSettings) {
+        self.settings = settings;
+    }
+
     pub fn reset(&mut self) {
         self.spawn_chance = self.settings.base_spawn_chance;
         self.base_chance = self.spawn_chance;
@@ -148,15 +214,21 @@ impl ObstaclePool {
         }
     }
 
-    pub fn has_collision(&self, player: &Player) -> bool {
-        let player_aabb = player.get_aabb();
+    pub fn distance_ahead(&self, x: f32) -> Option<f32> {
+        self.obstacles
+            .iter()
+            .map(|o| o.position.x - x)
+            .filter(|d| *d >= 0.)
+            .reduce(f32::min)
+    }
 
-        for obstacle in &self.obstacles {
-            let obstacle_aabb = obstacle.get_aabb();
-            if player_aabb.collides_with(&obstacle_aabb) {
-                return true;
-            }
-        }
-        false
+    // which of the players ran into an obstacle, the first one when several did
+    pub fn has_collision(&self, players: &[&Player]) -> Option<usize> {
+        players.iter().position(|player| {
+            let player_aabb = player.get_aabb();
+            self.obstacles
+                .iter()
+                .any(|obstacle| player_aabb.collides_with(&obstacle.get_aabb()))
+        })
     }
 }
@@ -0,0 +1,252 @@
+use crate::{
+    audio::Channel,
+    constants::*,
+    input::{Action, Binding},
+    locale::{Language, Locale},
+    settings::Settings,
+    ui::{Menu, UiEvent},
+    viewport::Viewport,
+};
+use macroquad::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+    DisplayMode,
+    ScaleMode,
+    HighDpi,
+    PostProcessing,
+    Volume(Channel),
+    Mute,
+    Difficulty,
+    ReduceMotion,
+    HighContrast,
+    Units,
+    MinimalHud,
+    Language,
+    Binding(Action),
+}
+
+fn rows() -> Vec<Row> {
+    let mut rows = vec![
+        Row::DisplayMode,
+        Row::ScaleMode,
+        Row::HighDpi,
+        Row::PostProcessing,
+    ];
+    rows.extend(Channel::ALL.iter().map(|channel| Row::Volume(*channel)));
+    rows.extend([
+        Row::Mute,
+        Row::Difficulty,
+        Row::ReduceMotion,
+        Row::HighContrast,
+        Row::Units,
+        Row::MinimalHud,
+        Row::Language,
+    ]);
+    rows.extend(Action::ALL.iter().map(|action| Row::Binding(*action)));
+    rows
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "common.on"
+    } else {
+        "common.off"
+    }
+}
+
+// cycles through following the system and then every language
+fn step_language(language: Option<Language>, direction: i32) -> Option<Language> {
+    let mut choices = vec![None];
+    choices.extend(Language::ALL.iter().copied().map(Some));
+    let i = choices.iter().position(|l| *l == language).unwrap_or(0) as i32;
+    choices[(i + direction).rem_euclid(choices.len() as i32) as usize]
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OptionsMenu {
+    pub rebinding: Option<Action>,
+    menu: Menu<Row>,
+}
+
+impl OptionsMenu {
+    pub fn open(&mut self) {
+        self.rebinding = None;
+        self.menu.reset_focus();
+    }
+
+    fn build(&mut self, settings: &Settings, locale: &Locale) {
+        self.menu.clear();
+        self.menu
+            .label(locale.text("options.title"), 64., PALETTE[15]);
+        for row in rows() {
+            match row {
+                Row::DisplayMode => self.menu.choice(
+                    row,
+                    locale.text("options.display"),
+                    locale.text(settings.display_mode.key()),
+                ),
+                Row::ScaleMode => self.menu.choice(
+                    row,
+                    locale.text("options.scaling"),
+                    locale.text(settings.scale_mode.key()),
+                ),
+                Row::HighDpi => self.menu.choice(
+                    row,
+                    locale.text("options.high_dpi"),
+                    &locale.format(
+                        "options.needs_restart",
+                        &[("value", locale.text(on_off(settings.high_dpi)))],
+                    ),
+                ),
+                Row::PostProcessing => self.menu.choice(
+                    row,
+                    locale.text("options.post_processing"),
+                    locale.text(settings.post_processing.key()),
+                ),
+                Row::Volume(channel) => self.menu.slider(
+                    row,
+                    locale.text(channel.key()),
+                    settings.audio.volume(channel),
+                ),
+                Row::Mute => self.menu.choice(
+                    row,
+                    locale.text("options.mute"),
+                    locale.text(on_off(settings.audio.muted)),
+                ),
+                Row::Difficulty => self.menu.choice(
+                    row,
+                    locale.text("options.difficulty"),
+                    locale.text(settings.difficulty.key()),
+                ),
+                Row::ReduceMotion => self.menu.choice(
+                    row,
+                    locale.text("options.reduce_motion"),
+                    locale.text(on_off(settings.reduce_motion)),
+                ),
+                Row::HighContrast => self.menu.choice(
+                    row,
+                    locale.text("options.high_contrast"),
+                    locale.text(on_off(settings.high_contrast)),
+                ),
+                Row::Units => self.menu.choice(
+                    row,
+                    locale.text("options.units"),
+                    locale.text(settings.units.key()),
+                ),
+                Row::MinimalHud => self.menu.choice(
+                    row,
+                    locale.text("options.minimal_hud"),
+                    locale.text(on_off(settings.minimal_hud)),
+                ),
+                Row::Language => self.menu.choice(
+                    row,
+                    locale.text("options.language"),
+                    &match settings.language {
+                        Some(language) => language.name().to_string(),
+                        None => locale.format(
+                            "options.language_auto",
+                            &[("language", locale.detected.name())],
+                        ),
+                    },
+                ),
+                Row::Binding(action) if self.rebinding == Some(action) => self.menu.choice(
+                    row,
+                    locale.text(action.key()),
+                    locale.text("options.rebind"),
+                ),
+                Row::Binding(action) => self.menu.choice(
+                    row,
+                    locale.text(action.key()),
+                    &settings.bindings.describe(action, locale),
+                ),
+            }
+        }
+        self.menu
+            .label(locale.text("options.hint"), 32., PALETTE[12]);
+    }
+
+    // returns true when a setting changed and needs to be applied and saved
+    pub fn tick(&mut self, settings: &mut Settings, viewport: &Viewport, locale: &Locale) -> bool {
+        if let Some(action) = self.rebinding {
+            if let Some(binding) = Binding::pressed() {
+                settings.bindings.rebind(action, binding);
+                self.rebinding = None;
+                self.build(settings, locale);
+                return true;
+            }
+            return false;
+        }
+
+        self.build(settings, locale);
+        let (row, direction) = match self.menu.tick(viewport) {
+            Some(UiEvent::Changed(row, direction)) => (row, direction),
+            Some(UiEvent::SliderSet(Row::Volume(channel), volume)) => {
+                settings.audio.set_volume(channel, volume);
+                return true;
+            }
+            _ => return false,
+        };
+
+        match row {
+            Row::DisplayMode => {
+                settings.display_mode = if direction > 0 {
+                    settings.display_mode.next()
+                } else {
+                    settings.display_mode.previous()
+                };
+            }
+            Row::ScaleMode => {
+                settings.scale_mode = if direction > 0 {
+                    settings.scale_mode.next()
+                } else {
+                    settings.scale_mode.previous()
+                };
+            }
+            Row::HighDpi => settings.high_dpi = !settings.high_dpi,
+            Row::PostProcessing => {
+                settings.post_processing = if direction > 0 {
+                    settings.post_processing.next()
+                } else {
+                    settings.post_processing.previous()
+                };
+            }
+            Row::Volume(channel) => {
+                let volume = settings.audio.volume(channel) + 0.1 * direction as f32;
+                settings.audio.set_volume(channel, volume);
+            }
+            Row::Mute => settings.audio.muted = !settings.audio.muted,
+            Row::Difficulty => {
+                settings.difficulty = if direction > 0 {
+                    settings.difficulty.next()
+                } else {
+                    settings.difficulty.previous()
+                };
+            }
+            Row::ReduceMotion => settings.reduce_motion = !settings.reduce_motion,
+            Row::HighContrast => settings.high_contrast = !settings.high_contrast,
+            Row::Units => {
+                settings.units = if direction > 0 {
+                    settings.units.next()
+                } else {
+                    settings.units.previous()
+                };
+            }
+            Row::MinimalHud => settings.minimal_hud = !settings.minimal_hud,
+            Row::Language => settings.language = step_language(settings.language, direction),
+            Row::Binding(action) => {
+                if direction > 0 {
+                    self.rebinding = Some(action);
+                    self.build(settings, locale);
+                }
+                return false;
+            }
+        }
+        self.build(settings, locale);
+        true
+    }
+
+    pub fn render(&self) {
+        self.menu.render();
+    }
+}
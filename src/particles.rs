@@ -0,0 +1,77 @@
+use crate::constants::*;
+use macroquad::{prelude::*, rand::gen_range};
+
+#[derive(Debug, Clone)]
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+    size: f32,
+    life: f32,
+    max_life: f32,
+    color: Color,
+}
+
+impl Particle {
+    pub fn step(&mut self) {
+        // velocity points up like the player's, screen space points down
+        self.velocity += *DOWN * *GRAVITY * 4. * TIMESTEP;
+        self.position += vec2(self.velocity.x, -self.velocity.y) * TIMESTEP;
+        self.life -= TIMESTEP;
+    }
+
+    pub fn render(&self) {
+        let t = (self.life / self.max_life).max(0.);
+        let mut color = self.color;
+        color.a = t;
+        draw_circle(self.position.x, self.position.y, self.size * t, color);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    capacity: usize,
+}
+
+impl ParticleSystem {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            particles: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn emit(&mut self, position: Vec2, direction: Vec2, color: Color) {
+        if self.particles.len() >= self.capacity {
+            self.particles.remove(0);
+        }
+
+        let spread = vec2(gen_range(-0.5, 0.5), gen_range(0., 1.));
+        let max_life = gen_range(0.3, 0.7);
+        self.particles.push(Particle {
+            position,
+            velocity: (direction + spread) * gen_range(40., 120.),
+            size: gen_range(4., 10.),
+            life: max_life,
+            max_life,
+            color,
+        });
+    }
+
+    pub fn step(&mut self) {
+        for particle in &mut self.particles {
+            particle.step();
+        }
+        self.particles.retain(|p| p.life > 0.);
+    }
+
+    pub fn render(&self) {
+        for particle in &self.particles {
+            particle.render();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+}
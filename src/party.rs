@@ -0,0 +1,179 @@
+use crate::{
+    constants::*,
+    input::{Binding, Bindings, PlayerInput},
+    obstacles::{ObstaclePool, ObstaclePoolSettings},
+    player::Player,
+    rng::Rng,
+    settings::Difficulty,
+};
+use macroquad::prelude::*;
+
+const JUMP_KEYS: [KeyCode; MAX_PARTY_SIZE] =
+    [KeyCode::Space, KeyCode::Enter, KeyCode::Q, KeyCode::P];
+
+// one of the riders sharing the trail, told apart by the color of their frame
+#[derive(Debug, Clone)]
+pub struct PartyRider {
+    pub player: Player,
+    pub bindings: Bindings,
+    pub color: Color,
+    pub palette: [Color; 16],
+    pub distance: f32,
+    // how long they lasted, set when they're out
+    pub time: f32,
+    pub is_out: bool,
+}
+
+impl PartyRider {
+    fn new(index: usize, resolution: Vec2) -> Self {
+        let color = party_color(index);
+        let mut palette = *PALETTE;
+        palette[1] = color;
+        let mut player = Player::new(vec2(128., 128.), resolution);
+        player.center.x -= index as f32 * PARTY_STAGGER;
+        player.reset();
+        Self {
+            player,
+            bindings: Bindings {
+                jump: vec![Binding::Key(JUMP_KEYS[index])],
+                pedal: Vec::new(),
+                brake: Vec::new(),
+                stroke_left: Vec::new(),
+                stroke_right: Vec::new(),
+                rewind: Vec::new(),
+            },
+            color,
+            palette,
+            distance: 0.,
+            time: 0.,
+            is_out: false,
+        }
+    }
+
+    fn knock_out(&mut self, round_time: f32) {
+        self.is_out = true;
+        self.time = round_time;
+        self.player.is_moving = false;
+    }
+}
+
+pub fn party_color(index: usize) -> Color {
+    [PALETTE[14], PALETTE[13], PALETTE[7], PALETTE[10]][index % MAX_PARTY_SIZE]
+}
+
+// up to four riders on one trail, staggered from the front. the trail scrolls at the pace of
+// whoever is in front, anyone left behind off the screen is out just like a crash
+#[derive(Clone)]
+pub struct Party {
+    pub riders: Vec<PartyRider>,
+    pub obstacles: ObstaclePool,
+    pub trees: ObstaclePool,
+    pub rng: Rng,
+    pub resolution: Vec2,
+    pub round_time: f32,
+    // how far the trail has scrolled
+    pub distance: f32,
+    pub seed: u64,
+}
+
+impl Party {
+    pub fn new() -> Self {
+        Self {
+            riders: Vec::new(),
+            obstacles: ObstaclePool::new(10, Some(Difficulty::Normal.obstacle_settings())),
+            trees: ObstaclePool::new(100, Some(ObstaclePoolSettings::trees())),
+            rng: Rng::new(0),
+            resolution: vec2(RESOLUTION_X, RESOLUTION_Y),
+            round_time: 0.,
+            distance: 0.,
+            seed: 0,
+        }
+    }
+
+    pub fn start(&mut self, seed: u64, size: usize, difficulty: Difficulty) {
+        let size = size.clamp(1, MAX_PARTY_SIZE);
+        self.riders = (0..size)
+            .map(|i| PartyRider::new(i, self.resolution))
+            .collect();
+        self.obstacles.configure(difficulty.obstacle_settings());
+        self.obstacles.reset();
+        self.trees.reset();
+        self.rng = Rng::new(seed);
+        self.round_time = 0.;
+        self.distance = 0.;
+        self.seed = seed;
+    }
+
+    pub fn tick(&mut self, delta_time: f32) {
+        for rider in self.riders.iter_mut().filter(|rider| !rider.is_out) {
+            let input = PlayerInput::poll(&rider.bindings);
+            rider.player.tick(&input);
+        }
+        self.round_time += delta_time;
+    }
+
+    // true when someone went out on this step
+    pub fn step(&mut self) -> bool {
+        let round_time = self.round_time;
+        for rider in self.riders.iter_mut().filter(|rider| !rider.is_out) {
+            rider.player.step(round_time);
+        }
+        let speed = self.leader().map_or(0., |leader| leader.player.speed);
+        self.obstacles.step(speed);
+        self.trees.step(speed * 0.7);
+        self.obstacles
+            .spawn_attempt(self.resolution, round_time, &mut self.rng);
+        self.trees
+            .spawn_attempt(self.resolution, round_time, &mut self.rng);
+        self.distance += speed;
+
+        let mut knocked_out = false;
+        for rider in self.riders.iter_mut() {
+            if rider.is_out {
+                // the fallen stay where they fell and scroll away with the trail
+                rider.player.center.x -= speed * PLAYER_SPEED_TO_OBSTACLE_SPEED;
+                continue;
+            }
+            rider.player.center.x += (rider.player.speed - speed) * PLAYER_SPEED_TO_OBSTACLE_SPEED;
+            rider.distance += rider.player.speed;
+            if rider.player.center.x + rider.player.size.x < 0. {
+                rider.knock_out(round_time);
+                knocked_out = true;
+            }
+        }
+
+        // several riders can hit something on the same step
+        loop {
+            let riding: Vec<usize> = (0..self.riders.len())
+                .filter(|i| !self.riders[*i].is_out)
+                .collect();
+            let players: Vec<&Player> = riding.iter().map(|i| &self.riders[*i].player).collect();
+            match self.obstacles.has_collision(&players) {
+                Some(hit) => {
+                    self.riders[riding[hit]].knock_out(round_time);
+                    knocked_out = true;
+                }
+                None => break,
+            }
+        }
+        knocked_out
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.riders.iter().all(|rider| rider.is_out)
+    }
+
+    // the rider furthest to the front that is still riding, the trail keeps their pace
+    pub fn leader(&self) -> Option<&PartyRider> {
+        self.riders
+            .iter()
+            .filter(|rider| !rider.is_out)
+            .max_by(|a, b| a.player.center.x.total_cmp(&b.player.center.x))
+    }
+}
+
+impl Default for Party {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,7 +1,15 @@
 use std::f32::consts::{PI, TAU};
 
-use crate::{constants::*, geometry::Rectangle, util::*};
-use macroquad::{prelude::*, audio::{Sound, play_sound_once, stop_sound}};
+use crate::{
+    audio::{Sfx, SoundEvent},
+    constants::*,
+    geometry::Rectangle,
+    input::{Action, PlayerInput},
+    particles::ParticleSystem,
+    util::*,
+};
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub struct Player {
@@ -15,18 +23,46 @@ pub struct Player {
     pub is_jumping: bool,
     pub can_jump: bool,
     pub is_moving: bool,
+    pub is_braking: bool,
+    is_skidding: bool,
+    pub stamina_mode: bool,
+    pub stamina: f32,
+    pub cadence: f32,
+    is_pedaling: bool,
+    last_stroke: Option<Action>,
     pedal_theta: f32,
     previous_pedal_theta: f32,
     wheel_theta: f32,
     previous_wheel_theta: f32,
+    rear_wheel_theta: f32,
     pub headlight: Vec2,
     pub taillight: Vec2,
-    jump_sound: Sound,
-    land_sound: Sound,
+    pub dust: ParticleSystem,
+    pub sound_events: Vec<SoundEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerState {
+    pub speed: f32,
+    pub ground_height: f32,
+    pub velocity: [f32; 2],
+    pub position: [f32; 2],
+    pub acceleration: [f32; 2],
+    pub is_jumping: bool,
+    pub can_jump: bool,
+    pub is_moving: bool,
+    pub is_braking: bool,
+    pub is_skidding: bool,
+    pub stamina_mode: bool,
+    pub stamina: f32,
+    pub cadence: f32,
+    pub pedal_theta: f32,
+    pub wheel_theta: f32,
+    pub rear_wheel_theta: f32,
 }
 
 impl Player {
-    pub fn new(size: Vec2, resolution: Vec2, jump: Sound, land: Sound) -> Self {
+    pub fn new(size: Vec2, resolution: Vec2) -> Self {
         Self {
             center: vec2((resolution.x - size.x) * 0.5, resolution.y - size.y),
             size,
@@ -38,14 +74,22 @@ impl Player {
             is_moving: true,
             is_jumping: false,
             can_jump: false,
+            is_braking: false,
+            is_skidding: false,
+            stamina_mode: false,
+            stamina: MAX_STAMINA,
+            cadence: 1.,
+            is_pedaling: false,
+            last_stroke: None,
             pedal_theta: 0.,
             previous_pedal_theta: 0.,
             wheel_theta: 0.,
             previous_wheel_theta: 0.,
+            rear_wheel_theta: 0.,
             headlight: vec2(0., 0.),
             taillight: vec2(0., 0.),
-            jump_sound: jump,
-            land_sound: land,
+            dust: ParticleSystem::new(64),
+            sound_events: Vec::new(),
         }
     }
 
@@ -58,9 +102,69 @@ impl Player {
         self.is_jumping = false;
         self.is_moving = true;
         self.can_jump = false;
+        self.is_braking = false;
+        self.is_skidding = false;
+        self.dust.clear();
+        self.stamina = MAX_STAMINA;
+        self.cadence = if self.stamina_mode { 0. } else { 1. };
+        self.is_pedaling = false;
+        self.last_stroke = None;
+    }
+
+    pub fn state(&self) -> PlayerState {
+        PlayerState {
+            speed: self.speed,
+            ground_height: self.ground_height,
+            velocity: self.velocity.into(),
+            position: self.position.into(),
+            acceleration: self.acceleration.into(),
+            is_jumping: self.is_jumping,
+            can_jump: self.can_jump,
+            is_moving: self.is_moving,
+            is_braking: self.is_braking,
+            is_skidding: self.is_skidding,
+            stamina_mode: self.stamina_mode,
+            stamina: self.stamina,
+            cadence: self.cadence,
+            pedal_theta: self.pedal_theta,
+            wheel_theta: self.wheel_theta,
+            rear_wheel_theta: self.rear_wheel_theta,
+        }
+    }
+
+    pub fn restore(&mut self, state: &PlayerState) {
+        if self.is_skidding && !state.is_skidding {
+            self.sound_events.push(SoundEvent::Stop(Sfx::Skid));
+        }
+        self.speed = state.speed;
+        self.ground_height = state.ground_height;
+        self.velocity = state.velocity.into();
+        self.position = state.position.into();
+        self.acceleration = state.acceleration.into();
+        self.is_jumping = state.is_jumping;
+        self.can_jump = state.can_jump;
+        self.is_moving = state.is_moving;
+        self.is_braking = state.is_braking;
+        self.is_skidding = state.is_skidding;
+        self.stamina_mode = state.stamina_mode;
+        self.stamina = state.stamina;
+        self.cadence = state.cadence;
+        self.is_pedaling = false;
+        self.last_stroke = None;
+        self.pedal_theta = state.pedal_theta;
+        self.previous_pedal_theta = state.pedal_theta;
+        self.wheel_theta = state.wheel_theta;
+        self.previous_wheel_theta = state.wheel_theta;
+        self.rear_wheel_theta = state.rear_wheel_theta;
+        self.dust.clear();
     }
 
     pub fn render(&mut self) {
+        self.render_with(&PALETTE);
+    }
+
+    // the same bike in other colors, ghosts pass a faded palette
+    pub fn render_with(&mut self, palette: &[Color; 16]) {
         let line_thickness = 8.;
         let half_line_thickness = line_thickness * 0.5;
         let wheel_radius = 24.;
@@ -71,6 +175,20 @@ impl Player {
             );
         let wheel_1 = center - vec2(40., -line_thickness);
         let wheel_2 = center + vec2(40., line_thickness);
+
+        self.dust.render();
+        if self.is_skidding {
+            let ground = wheel_1.y + wheel_radius;
+            draw_line(
+                wheel_1.x - wheel_radius * 3.,
+                ground,
+                wheel_1.x,
+                ground,
+                line_thickness * 0.5,
+                palette[3],
+            );
+        }
+
         let bottom_bracket = wheel_1 + vec2((wheel_2.x - wheel_1.x) * 0.5, 0.);
 
         let seat_post = wheel_1 + vec2((bottom_bracket.x - wheel_1.x) * 0.5, -wheel_radius * 1.5);
@@ -98,7 +216,7 @@ impl Player {
             crank_2.x,
             crank_2.y,
             line_thickness * 0.5,
-            PALETTE[12],
+            palette[12],
         );
         draw_line(
             pedal_2_start.x,
@@ -106,7 +224,7 @@ impl Player {
             pedal_2_end.x,
             pedal_2_end.y,
             line_thickness * 0.75,
-            PALETTE[0],
+            palette[0],
         );
 
         draw_circle_lines(
@@ -114,29 +232,31 @@ impl Player {
             wheel_1.y,
             wheel_radius,
             line_thickness,
-            PALETTE[0],
+            palette[0],
         );
         draw_circle_lines(
             wheel_2.x,
             wheel_2.y,
             wheel_radius,
             line_thickness,
-            PALETTE[0],
+            palette[0],
         );
         let spokes = 16.;
 
         
 
         let increment = TAU / spokes;
-        let mut theta = self.wheel_theta;
+        let mut theta_1 = self.rear_wheel_theta;
+        let mut theta_2 = self.wheel_theta;
         for _i in 0..(spokes as usize) {
             let point_1 =
-                point_on_circle(wheel_1, wheel_radius - line_thickness * 0.5, theta % TAU);
+                point_on_circle(wheel_1, wheel_radius - line_thickness * 0.5, theta_1 % TAU);
             let point_2 =
-                point_on_circle(wheel_2, wheel_radius - line_thickness * 0.5, theta % TAU);
-            draw_line(wheel_1.x, wheel_1.y, point_1.x, point_1.y, 1., PALETTE[12]);
-            draw_line(wheel_2.x, wheel_2.y, point_2.x, point_2.y, 1., PALETTE[12]);
-            theta += increment;
+                point_on_circle(wheel_2, wheel_radius - line_thickness * 0.5, theta_2 % TAU);
+            draw_line(wheel_1.x, wheel_1.y, point_1.x, point_1.y, 1., palette[12]);
+            draw_line(wheel_2.x, wheel_2.y, point_2.x, point_2.y, 1., palette[12]);
+            theta_1 += increment;
+            theta_2 += increment;
         }
 
         draw_line(
@@ -145,7 +265,7 @@ impl Player {
             bottom_bracket.x,
             bottom_bracket.y,
             line_thickness,
-            PALETTE[1],
+            palette[1],
         );
         draw_line(
             wheel_1.x - half_line_thickness,
@@ -153,7 +273,7 @@ impl Player {
             seat_post.x,
             seat_post.y,
             line_thickness,
-            PALETTE[1],
+            palette[1],
         );
         draw_line(
             seat_post.x,
@@ -161,7 +281,7 @@ impl Player {
             bottom_bracket.x,
             bottom_bracket.y,
             line_thickness,
-            PALETTE[1],
+            palette[1],
         );
         draw_line(
             bottom_bracket.x,
@@ -169,7 +289,7 @@ impl Player {
             steering_tube.x,
             steering_tube.y,
             line_thickness,
-            PALETTE[1],
+            palette[1],
         );
         draw_line(
             seat_post.x,
@@ -177,7 +297,7 @@ impl Player {
             steering_tube.x,
             steering_tube.y,
             line_thickness,
-            PALETTE[1],
+            palette[1],
         );
         draw_line(
             steering_tube.x,
@@ -185,7 +305,7 @@ impl Player {
             wheel_2.x,
             wheel_2.y,
             line_thickness + 2.,
-            PALETTE[1],
+            palette[1],
         );
 
         let lamp_front = steering_tube + vec2(line_thickness, line_thickness * 0.75);
@@ -197,9 +317,9 @@ impl Player {
             lamp_front.x,
             lamp_front.y,
             line_thickness * 0.5,
-            PALETTE[14],
+            palette[14],
         );
-        draw_circle(lamp_back.x, lamp_back.y, line_thickness * 0.5, PALETTE[8]);
+        draw_circle(lamp_back.x, lamp_back.y, line_thickness * 0.5, palette[8]);
 
         draw_line(
             seat_start.x,
@@ -207,21 +327,21 @@ impl Player {
             seat_end.x,
             seat_end.y,
             line_thickness + 4.,
-            PALETTE[4],
+            palette[4],
         );
-        draw_circle(wheel_1.x, wheel_1.y, line_thickness, PALETTE[9]);
-        draw_circle(wheel_2.x, wheel_2.y, line_thickness, PALETTE[9]);
+        draw_circle(wheel_1.x, wheel_1.y, line_thickness, palette[9]);
+        draw_circle(wheel_2.x, wheel_2.y, line_thickness, palette[9]);
         draw_circle(
             bottom_bracket.x,
             bottom_bracket.y,
             line_thickness * 0.5,
-            PALETTE[1],
+            palette[1],
         );
         draw_circle(
             steering_tube.x,
             steering_tube.y,
             line_thickness * 0.5,
-            PALETTE[1],
+            palette[1],
         );
         draw_line(
             steering_tube.x,
@@ -229,9 +349,9 @@ impl Player {
             steer.x,
             steer.y,
             line_thickness,
-            PALETTE[1],
+            palette[1],
         );
-        draw_circle(steer.x, steer.y, 0.75 * line_thickness, PALETTE[0]);
+        draw_circle(steer.x, steer.y, 0.75 * line_thickness, palette[0]);
 
         draw_line(
             bottom_bracket.x,
@@ -239,16 +359,16 @@ impl Player {
             crank_1.x,
             crank_1.y,
             line_thickness * 0.5,
-            PALETTE[12],
+            palette[12],
         );
-        draw_circle(bottom_bracket.x, bottom_bracket.y, 4., PALETTE[12]);
+        draw_circle(bottom_bracket.x, bottom_bracket.y, 4., palette[12]);
         draw_line(
             pedal_1_start.x,
             pedal_1_start.y,
             pedal_1_end.x,
             pedal_1_end.y,
             line_thickness * 0.75,
-            PALETTE[0],
+            palette[0],
         );
     }
 
@@ -258,11 +378,13 @@ impl Player {
             self.acceleration += *UP * *GRAVITY;
 
             if self.is_jumping {
+                let fall_velocity = -self.velocity.y;
                 self.acceleration = *UP * *GRAVITY;
                 self.velocity = Vec2::ZERO;
                 self.is_jumping = false;
-                stop_sound(self.jump_sound);
-                play_sound_once(self.land_sound);
+                self.sound_events.push(SoundEvent::Stop(Sfx::Jump));
+                self.sound_events.push(SoundEvent::Play(Sfx::Land));
+                self.sound_events.push(SoundEvent::Landing(fall_velocity));
             }
             self.can_jump = true;
 
@@ -275,35 +397,120 @@ impl Player {
         self.velocity += self.acceleration * TIMESTEP;
         self.position += self.velocity * TIMESTEP;
 
-        self.speed += 0.0001;
+        let wheel_factor = if self.stamina_mode {
+            self.step_stamina();
+            self.speed / DEFAULT_PLAYER_SPEED
+        } else {
+            if !self.is_braking {
+                self.speed += 0.0001;
+            }
+            1.
+        };
+        self.step_brakes();
 
         self.previous_wheel_theta = self.wheel_theta;
         let wheel_speed = if self.is_jumping { 15. } else { 30. };
         self.wheel_theta = if self.is_moving {
-            (self.previous_wheel_theta + (TAU / wheel_speed)) % TAU
+            (self.previous_wheel_theta + (TAU / wheel_speed) * wheel_factor) % TAU
         } else {
             self.previous_wheel_theta
         };
+        // a skidding rear wheel is locked
+        if !self.is_skidding {
+            self.rear_wheel_theta =
+                (self.rear_wheel_theta + self.wheel_theta - self.previous_wheel_theta) % TAU;
+        }
 
         self.previous_pedal_theta = self.pedal_theta;
         self.pedal_theta = if self.is_moving && !self.is_jumping {
-            (self.previous_pedal_theta + (TAU / 30.)) % TAU
+            (self.previous_pedal_theta + (TAU / 30.) * self.cadence) % TAU
         } else {
             self.previous_pedal_theta
         };
+
+        // a chain tick every half crank revolution
+        let crossed_half = self.previous_pedal_theta < PI && self.pedal_theta >= PI;
+        let crossed_full = self.pedal_theta < self.previous_pedal_theta;
+        if crossed_half || crossed_full {
+            self.sound_events.push(SoundEvent::Chain);
+        }
+    }
+
+    fn step_stamina(&mut self) {
+        if self.is_pedaling && self.stamina > 0. {
+            self.cadence += (1. - self.cadence) * 0.1;
+        }
+        self.cadence *= CADENCE_DECAY;
+
+        // you can't push the pedals while airborne
+        let effort = if self.is_jumping || !self.is_moving {
+            0.
+        } else {
+            self.cadence
+        };
+        self.speed +=
+            effort * PEDAL_ACCELERATION - (self.speed - DEFAULT_PLAYER_SPEED) * COAST_DRAG;
+        self.speed = self.speed.max(MIN_PLAYER_SPEED);
+
+        self.stamina -= effort * STAMINA_DRAIN;
+        self.stamina += (1. - effort) * STAMINA_RECOVERY;
+        self.stamina = self.stamina.clamp(0., MAX_STAMINA);
+    }
+
+    fn step_brakes(&mut self) {
+        let is_skidding =
+            self.is_braking && self.is_moving && !self.is_jumping && self.speed > MIN_PLAYER_SPEED;
+
+        if is_skidding {
+            self.speed = (self.speed - BRAKE_DECELERATION).max(MIN_PLAYER_SPEED);
+            self.dust
+                .emit(self.rear_contact(), vec2(-1., 0.5), PALETTE[11]);
+        }
+
+        if is_skidding && !self.is_skidding {
+            self.sound_events.push(SoundEvent::Play(Sfx::Skid));
+        } else if !is_skidding && self.is_skidding {
+            self.sound_events.push(SoundEvent::Stop(Sfx::Skid));
+        }
+        self.is_skidding = is_skidding;
+        self.dust.step();
     }
 
-    pub fn tick(&mut self) {
-        if is_mouse_button_down(MouseButton::Left) && self.can_jump {
+    pub fn tick(&mut self, input: &PlayerInput) {
+        if input.jump && self.can_jump {
             if !self.is_jumping {
                 self.jump();
-                play_sound_once(self.jump_sound);
+                self.sound_events.push(SoundEvent::Play(Sfx::Jump));
             }
         }
 
-        if is_mouse_button_released(MouseButton::Left) && self.is_jumping && self.can_jump {
+        if input.jump_released && self.is_jumping && self.can_jump {
             self.can_jump = false;
         }
+
+        self.is_braking = input.brake;
+
+        if self.stamina_mode {
+            self.tick_pedals(input);
+        }
+    }
+
+    fn tick_pedals(&mut self, input: &PlayerInput) {
+        self.is_pedaling = input.pedal;
+
+        // alternating left/right strokes push harder than holding
+        let strokes = [
+            (Action::StrokeLeft, input.stroke_left),
+            (Action::StrokeRight, input.stroke_right),
+        ];
+        for (stroke, pressed) in strokes {
+            if pressed && self.last_stroke != Some(stroke) {
+                self.last_stroke = Some(stroke);
+                if self.stamina > 0. {
+                    self.cadence = (self.cadence + PEDAL_STROKE).min(1.);
+                }
+            }
+        }
     }
 
     fn jump(&mut self) {
@@ -316,6 +523,10 @@ impl Player {
         Rectangle::new(self.origin(), self.size)
     }
 
+    fn rear_contact(&self) -> Vec2 {
+        self.center - self.position + vec2(self.size.x * 0.5 - 40., self.size.y)
+    }
+
     fn origin(&self) -> Vec2 {
         vec2(
             self.center.x,
@@ -0,0 +1,188 @@
+use crate::{achievements::RunStats, constants::*, storage};
+use serde::{Deserialize, Serialize};
+use std::io;
+
+const PROFILE_KEY: &str = "profile";
+const EXPORT_FILE_NAME: &str = "mtb-profile.json";
+
+// lifetime statistics over every solo run that ended in a crash
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub runs: u32,
+    // meters
+    pub total_distance: f32,
+    // seconds
+    pub total_time: f32,
+    pub total_jumps: u32,
+    pub crashes: u32,
+    // seconds
+    pub longest_airtime: f32,
+    pub nights_survived: u32,
+    // how many runs ended in each stretch of DEATH_HISTOGRAM_BUCKET meters, from the start on
+    pub deaths: Vec<u32>,
+}
+
+// what the balancing spreadsheets get, the profile with the averages worked out
+#[derive(Serialize)]
+struct ProfileExport<'a> {
+    #[serde(flatten)]
+    profile: &'a Profile,
+    average_distance: f32,
+    average_time: f32,
+    death_bucket: f32,
+}
+
+impl Profile {
+    pub fn load() -> Self {
+        storage::load(PROFILE_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save(PROFILE_KEY, &serde_json::to_string(self).unwrap()) {
+            macroquad::logging::error!("saving the profile failed: {}", e);
+        }
+    }
+
+    // adds a crash to the totals. a run rewound or continued after crashing goes on, so only
+    // what happened since the last time it was recorded is counted again
+    pub fn record(&mut self, stats: &RunStats, recorded: &RunStats) {
+        if !recorded.crashed {
+            self.runs += 1;
+        }
+        self.total_distance += (stats.distance - recorded.distance).max(0.);
+        self.total_time += (stats.time - recorded.time).max(0.);
+        self.total_jumps += stats.jumps.saturating_sub(recorded.jumps);
+        self.nights_survived += stats
+            .day_night_cycles()
+            .saturating_sub(recorded.day_night_cycles());
+        self.longest_airtime = self.longest_airtime.max(stats.longest_airtime);
+        self.crashes += 1;
+
+        let bucket = (stats.distance.max(0.) / DEATH_HISTOGRAM_BUCKET) as usize;
+        if self.deaths.len() <= bucket {
+            self.deaths.resize(bucket + 1, 0);
+        }
+        self.deaths[bucket] += 1;
+    }
+
+    pub fn average_distance(&self) -> f32 {
+        self.total_distance / self.runs.max(1) as f32
+    }
+
+    pub fn average_time(&self) -> f32 {
+        self.total_time / self.runs.max(1) as f32
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&ProfileExport {
+            profile: self,
+            average_distance: self.average_distance(),
+            average_time: self.average_time(),
+            death_bucket: DEATH_HISTOGRAM_BUCKET,
+        })
+        .unwrap()
+    }
+
+    pub fn export(&self) -> io::Result<String> {
+        storage::export(EXPORT_FILE_NAME, &self.to_json())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crashed_at(distance: f32, time: f32, jumps: u32) -> RunStats {
+        let mut stats = RunStats::default();
+        stats.distance = distance;
+        stats.time = time;
+        stats.jumps = jumps;
+        stats.crash();
+        stats
+    }
+
+    #[test]
+    fn runs_add_up() {
+        let mut profile = Profile::default();
+        let mut first = crashed_at(300., 20., 4);
+        first.longest_airtime = 0.8;
+        let mut second = crashed_at(100., DAY_NIGHT_CYCLE_TIME * 2. + 1., 6);
+        second.longest_airtime = 0.5;
+        profile.record(&first, &RunStats::default());
+        profile.record(&second, &RunStats::default());
+
+        assert_eq!(profile.runs, 2);
+        assert_eq!(profile.total_distance, 400.);
+        assert_eq!(profile.total_time, 20. + DAY_NIGHT_CYCLE_TIME * 2. + 1.);
+        assert_eq!(profile.total_jumps, 10);
+        assert_eq!(profile.longest_airtime, 0.8);
+        assert_eq!(profile.nights_survived, 2);
+        assert_eq!(profile.crashes, 2);
+        assert_eq!(profile.average_distance(), 200.);
+    }
+
+    #[test]
+    fn a_rewound_run_is_counted_once() {
+        let mut profile = Profile::default();
+        let first = crashed_at(300., 20., 4);
+        profile.record(&first, &RunStats::default());
+        // rewound to 250m and crashed again further on
+        let second = crashed_at(400., 27., 6);
+        profile.record(&second, &first);
+
+        assert_eq!(profile.runs, 1);
+        assert_eq!(profile.total_distance, 400.);
+        assert_eq!(profile.total_time, 27.);
+        assert_eq!(profile.total_jumps, 6);
+        assert_eq!(profile.crashes, 2);
+        assert_eq!(profile.deaths, vec![0, 2]);
+        // every crash counts, wherever it was
+        let third = crashed_at(350., 25., 6);
+        profile.record(&third, &second);
+        assert_eq!(profile.runs, 1);
+        assert_eq!(profile.total_distance, 400.);
+        assert_eq!(profile.total_jumps, 6);
+        assert_eq!(profile.deaths, vec![0, 3]);
+    }
+
+    #[test]
+    fn deaths_are_bucketed_by_distance() {
+        let mut profile = Profile::default();
+        for distance in [
+            0.,
+            DEATH_HISTOGRAM_BUCKET - 1.,
+            DEATH_HISTOGRAM_BUCKET,
+            DEATH_HISTOGRAM_BUCKET * 3.5,
+        ] {
+            profile.record(&crashed_at(distance, 1., 0), &RunStats::default());
+        }
+        assert_eq!(profile.deaths, vec![2, 1, 0, 1]);
+    }
+
+    #[test]
+    fn no_runs_average_to_nothing() {
+        let profile = Profile::default();
+        assert_eq!(profile.average_distance(), 0.);
+        assert_eq!(profile.average_time(), 0.);
+        assert!(profile.deaths.is_empty());
+    }
+
+    #[test]
+    fn the_export_has_the_averages() {
+        let mut profile = Profile::default();
+        profile.record(&crashed_at(300., 20., 4), &RunStats::default());
+        profile.record(&crashed_at(100., 10., 2), &RunStats::default());
+        let export: serde_json::Value = serde_json::from_str(&profile.to_json()).unwrap();
+        assert_eq!(export["runs"], 2);
+        assert_eq!(export["average_distance"], 200.);
+        assert_eq!(export["average_time"], 15.);
+        assert_eq!(export["death_bucket"], DEATH_HISTOGRAM_BUCKET as f64);
+        assert_eq!(export["crashes"], 2);
+        assert_eq!(export["deaths"], serde_json::json!([1, 1]));
+        // and it reads back as a profile
+        assert_eq!(serde_json::from_value::<Profile>(export).unwrap(), profile);
+    }
+}
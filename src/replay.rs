@@ -0,0 +1,106 @@
+use crate::{input::PlayerInput, settings::Difficulty};
+use serde::{Deserialize, Serialize};
+
+pub const REPLAY_VERSION: u32 = 1;
+
+const JUMP: u8 = 1;
+const JUMP_RELEASED: u8 = 1 << 1;
+const PEDAL: u8 = 1 << 2;
+const BRAKE: u8 = 1 << 3;
+const STROKE_LEFT: u8 = 1 << 4;
+const STROKE_RIGHT: u8 = 1 << 5;
+
+// one rendered frame of a ride: how long it took, what was held and how many steps followed,
+// stored as [delta_time, input, steps] to keep submissions small
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "(f32, u8, u32)", from = "(f32, u8, u32)")]
+pub struct ReplayFrame {
+    pub delta_time: f32,
+    pub input: PlayerInput,
+    pub steps: u32,
+}
+
+impl From<ReplayFrame> for (f32, u8, u32) {
+    fn from(frame: ReplayFrame) -> Self {
+        (frame.delta_time, pack(&frame.input), frame.steps)
+    }
+}
+
+impl From<(f32, u8, u32)> for ReplayFrame {
+    fn from((delta_time, input, steps): (f32, u8, u32)) -> Self {
+        Self {
+            delta_time,
+            input: unpack(input),
+            steps,
+        }
+    }
+}
+
+fn pack(input: &PlayerInput) -> u8 {
+    [
+        (input.jump, JUMP),
+        (input.jump_released, JUMP_RELEASED),
+        (input.pedal, PEDAL),
+        (input.brake, BRAKE),
+        (input.stroke_left, STROKE_LEFT),
+        (input.stroke_right, STROKE_RIGHT),
+    ]
+    .iter()
+    .filter(|(held, _)| *held)
+    .fold(0, |bits, (_, bit)| bits | bit)
+}
+
+fn unpack(bits: u8) -> PlayerInput {
+    PlayerInput {
+        jump: bits & JUMP != 0,
+        jump_released: bits & JUMP_RELEASED != 0,
+        pedal: bits & PEDAL != 0,
+        brake: bits & BRAKE != 0,
+        stroke_left: bits & STROKE_LEFT != 0,
+        stroke_right: bits & STROKE_RIGHT != 0,
+    }
+}
+
+// everything needed to ride a run again without a window, see verify::verify
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub stamina_mode: bool,
+    pub frames: Vec<ReplayFrame>,
+    // frames the run picked up from after a pause
+    #[serde(default)]
+    pub resumes: Vec<usize>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, difficulty: Difficulty, stamina_mode: bool) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            seed,
+            difficulty,
+            stamina_mode,
+            frames: Vec::new(),
+            resumes: Vec::new(),
+        }
+    }
+
+    pub fn record_frame(&mut self, input: &PlayerInput, delta_time: f32) {
+        self.frames.push(ReplayFrame {
+            delta_time,
+            input: *input,
+            steps: 0,
+        });
+    }
+
+    pub fn resume(&mut self) {
+        self.resumes.push(self.frames.len());
+    }
+
+    pub fn record_step(&mut self) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.steps += 1;
+        }
+    }
+}
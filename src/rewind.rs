@@ -0,0 +1,51 @@
+use crate::{constants::*, snapshot::Snapshot};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone)]
+pub struct Rewind {
+    frames: VecDeque<Snapshot>,
+    capacity: usize,
+    pub meter: f32,
+}
+
+impl Rewind {
+    pub fn new(seconds: f32) -> Self {
+        let capacity = (seconds * TPS) as usize;
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+            meter: REWIND_METER,
+        }
+    }
+
+    pub fn record(&mut self, snapshot: Snapshot) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(snapshot);
+        self.meter = (self.meter + REWIND_RECOVERY * TIMESTEP).min(REWIND_METER);
+    }
+
+    // walks back one step, returning None once the buffer or the meter runs out
+    pub fn step_back(&mut self) -> Option<Snapshot> {
+        if self.meter <= 0. {
+            return None;
+        }
+        let snapshot = self.frames.pop_back()?;
+        self.meter = (self.meter - TIMESTEP).max(0.);
+        Some(snapshot)
+    }
+
+    pub fn can_rewind(&self) -> bool {
+        self.meter > 0. && !self.frames.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    pub fn reset(&mut self) {
+        self.frames.clear();
+        self.meter = REWIND_METER;
+    }
+}
@@ -0,0 +1,46 @@
+const MULTIPLIER: u64 = 6364136223846793005;
+const INCREMENT: u64 = 1442695040888963407;
+
+// Same PCG32 as macroquad::rand, but owned so its state can be stored and restored
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn from_state(state: u64) -> Self {
+        Self { state }
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old_state = self.state;
+        self.state = old_state.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+        let xorshifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+        let rot = (old_state >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    pub fn gen_f32(&mut self, low: f32, high: f32) -> f32 {
+        let r = self.next_u32() as f32 / u32::MAX as f32;
+        low + (high - low) * r
+    }
+
+    pub fn gen_i32(&mut self, low: i32, high: i32) -> i32 {
+        if high <= low {
+            return low;
+        }
+        low + (self.next_u32() % (high - low) as u32) as i32
+    }
+}
@@ -0,0 +1,102 @@
+use crate::storage;
+use serde::{Deserialize, Serialize};
+
+const HIGH_SCORES_KEY: &str = "high-scores";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    pub distance: f32,
+    pub time: f32,
+    pub assisted: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct HighScores {
+    scores: Vec<Score>,
+    capacity: usize,
+}
+
+impl HighScores {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            scores: Vec::with_capacity(capacity + 1),
+            capacity,
+        }
+    }
+
+    pub fn load(capacity: usize) -> Self {
+        storage::load(HIGH_SCORES_KEY)
+            .map(|json| Self::from_json(&json, capacity))
+            .unwrap_or_else(|| Self::new(capacity))
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save(HIGH_SCORES_KEY, &self.to_json()) {
+            macroquad::logging::error!("saving high scores failed: {}", e);
+        }
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(&self.scores).unwrap()
+    }
+
+    // a list saved with a larger capacity keeps its best entries
+    fn from_json(json: &str, capacity: usize) -> Self {
+        let mut high_scores = Self::new(capacity);
+        let mut scores: Vec<Score> = serde_json::from_str(json).unwrap_or_default();
+        scores.truncate(capacity);
+        high_scores.scores = scores;
+        high_scores
+    }
+
+    // returns the rank of the score if it made the list
+    pub fn submit(&mut self, score: Score) -> Option<usize> {
+        let rank = self
+            .scores
+            .iter()
+            .position(|s| score.distance > s.distance)
+            .unwrap_or(self.scores.len());
+        if rank >= self.capacity {
+            return None;
+        }
+        self.scores.insert(rank, score);
+        self.scores.truncate(self.capacity);
+        Some(rank)
+    }
+
+    pub fn best(&self) -> Option<&Score> {
+        self.scores.first()
+    }
+
+    pub fn scores(&self) -> &[Score] {
+        &self.scores
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(distance: f32, assisted: bool) -> Score {
+        Score {
+            distance,
+            time: distance / 10.,
+            assisted,
+        }
+    }
+
+    #[test]
+    fn high_scores_survive_a_save_and_load() {
+        let mut high_scores = HighScores::new(3);
+        for distance in [120., 480., 60., 300.].iter() {
+            high_scores.submit(score(*distance, *distance == 60.));
+        }
+        let loaded = HighScores::from_json(&high_scores.to_json(), 3);
+        assert_eq!(loaded.scores(), high_scores.scores());
+        assert_eq!(loaded.best(), Some(&score(480., false)));
+
+        let smaller = HighScores::from_json(&high_scores.to_json(), 2);
+        assert_eq!(smaller.scores(), &high_scores.scores()[..2]);
+        assert!(HighScores::from_json("not json", 3).scores().is_empty());
+    }
+}
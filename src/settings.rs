@@ -0,0 +1,178 @@
+use crate::{
+    audio::AudioSettings, constants::*, input::Bindings, locale::Language,
+    obstacles::ObstaclePoolSettings, storage, units::Units, viewport::ScaleMode,
+};
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_KEY: &str = "settings";
+// where the audio settings were kept before they joined the rest
+const LEGACY_AUDIO_KEY: &str = "audio";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    Windowed,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub fn key(&self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "display.windowed",
+            DisplayMode::Fullscreen => "display.fullscreen",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            DisplayMode::Windowed => DisplayMode::Fullscreen,
+            DisplayMode::Fullscreen => DisplayMode::Windowed,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        self.next()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PostProcessing {
+    Off,
+    Low,
+    High,
+}
+
+impl PostProcessing {
+    pub fn key(&self) -> &'static str {
+        match self {
+            PostProcessing::Off => "post_processing.off",
+            PostProcessing::Low => "post_processing.low",
+            PostProcessing::High => "post_processing.high",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            PostProcessing::Off => PostProcessing::Low,
+            PostProcessing::Low => PostProcessing::High,
+            PostProcessing::High => PostProcessing::Off,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        self.next().next()
+    }
+
+    // low keeps the lights but drops the vignette by pushing it off screen
+    pub fn vignette_radius(&self) -> f32 {
+        match self {
+            PostProcessing::High => VIGNETTE_RADIUS,
+            _ => 2.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn key(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "difficulty.easy",
+            Difficulty::Normal => "difficulty.normal",
+            Difficulty::Hard => "difficulty.hard",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        self.next().next()
+    }
+
+    pub fn obstacle_settings(&self) -> ObstaclePoolSettings {
+        let (base_spawn_chance, spawn_interval) = match self {
+            Difficulty::Easy => (0.05, 3.),
+            Difficulty::Normal => (0.1, 2.),
+            Difficulty::Hard => (0.2, 1.5),
+        };
+        ObstaclePoolSettings {
+            base_spawn_chance,
+            spawn_interval,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub display_mode: DisplayMode,
+    pub scale_mode: ScaleMode,
+    pub high_dpi: bool,
+    pub post_processing: PostProcessing,
+    pub audio: AudioSettings,
+    pub bindings: Bindings,
+    pub difficulty: Difficulty,
+    pub reduce_motion: bool,
+    pub high_contrast: bool,
+    pub units: Units,
+    pub minimal_hud: bool,
+    // none follows the system or browser language
+    pub language: Option<Language>,
+    // an online leaderboard to submit runs to, none keeps scores on this device
+    pub leaderboard_url: Option<String>,
+    pub player_name: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            display_mode: DisplayMode::Fullscreen,
+            scale_mode: ScaleMode::Letterbox,
+            high_dpi: true,
+            post_processing: PostProcessing::High,
+            audio: AudioSettings::default(),
+            bindings: Bindings::default(),
+            difficulty: Difficulty::Normal,
+            reduce_motion: false,
+            high_contrast: false,
+            units: Units::Metric,
+            minimal_hud: false,
+            language: None,
+            leaderboard_url: None,
+            player_name: "RIDER".to_string(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let mut settings: Self = storage::load(SETTINGS_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        if let Some(json) = storage::load(LEGACY_AUDIO_KEY) {
+            if let Ok(audio) = serde_json::from_str(&json) {
+                settings.audio = audio;
+                settings.save();
+            }
+            storage::remove(LEGACY_AUDIO_KEY);
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save(SETTINGS_KEY, &serde_json::to_string(self).unwrap()) {
+            macroquad::logging::error!("saving settings failed: {}", e);
+        }
+    }
+}
@@ -56,6 +56,8 @@ pub fn get_post_processing_material() -> Material {
                 ("taillight".to_string(), UniformType::Float2),
                 ("taillight_color".to_string(), UniformType::Float3),
                 ("darkness".to_string(), UniformType::Float1),
+                ("desaturation".to_string(), UniformType::Float1),
+                ("scanlines".to_string(), UniformType::Float1),
             ],
             ..Default::default()
         },
@@ -66,5 +68,6 @@ pub fn get_post_processing_material() -> Material {
 
     material.set_uniform("radius", VIGNETTE_RADIUS);
     material.set_uniform("smoothness", VIGNETTE_SMOOTHNESS);
+    material.set_uniform("scanlines", 1.);
     material
 }
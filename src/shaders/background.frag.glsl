@@ -72,7 +72,8 @@ void main() {
     float n2 = mountain2(gl_FragCoord.x);
     float ypos = gl_FragCoord.y / iResolution.y;
     float y = 2.0 * (ypos) - 1.0;
-    vec3 color = mix(sky_gradient_end, sky_gradient_start, ypos / 1.2);
+    // upright views reach above the trail's sky, it keeps the color it has at the top
+    vec3 color = mix(sky_gradient_end, sky_gradient_start, min(ypos / 1.2, 1.));
     vec4 sun = circle(position_sun, radius_sun, color_sun);
     color = mix(color, sun.rgb, sun.a);
     if(n2 > y)
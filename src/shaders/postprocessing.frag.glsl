@@ -14,6 +14,8 @@ uniform vec2 headlight;
 uniform vec3 headlight_color;
 uniform vec2 taillight;
 uniform vec3 taillight_color;
+uniform float desaturation;
+uniform float scanlines;
 
 vec3 pointlight(vec3 color, float range, vec2 position) {
     vec2 directionToLight = normalize(position);
@@ -27,6 +29,11 @@ vec3 spotlight(vec3 color, float range, vec2 position, vec2 direction) {
     return color * pow(max(dot(normalize(direction), normalize(position)), 0.), range);
 }
 
+vec3 desaturate(vec3 color, float amount) {
+    float luma = dot(color, vec3(0.299, 0.587, 0.114));
+    return mix(color, vec3(luma), amount);
+}
+
 float vignette(vec2 uv, float radius, float smoothness) {
     float diff = radius - distance(uv, vec2(0.5, 0.5));
     return smoothstep(-smoothness, smoothness, diff);
@@ -37,5 +44,8 @@ void main() {
     color += spotlight(headlight_color, 90., uv - headlight, vec2(.2, -.025)) * darkness;
     color += pointlight(taillight_color, 100., taillight - uv) * darkness;
     color *= vignette(uv, radius, smoothness);
+    color = desaturate(color, desaturation);
+    // faint rolling scanlines while rewinding
+    color *= 1. - desaturation * scanlines * 0.15 * step(0.5, fract(uv.y * iResolution.y * 0.25 + iTime * 8.));
     gl_FragColor = vec4(color, 1.);
 }
\ No newline at end of file
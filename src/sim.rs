@@ -0,0 +1,128 @@
+use crate::{
+    achievements::RunStats,
+    constants::*,
+    input::PlayerInput,
+    obstacles::{ObstaclePool, ObstaclePoolSettings},
+    player::Player,
+    replay::Replay,
+    rng::Rng,
+    settings::Difficulty,
+};
+use macroquad::prelude::*;
+
+// one step of the ride itself, shared with Game::step so replays can't drift from the game.
+// replays have to ride to the same bits on desktop and web, so only + - * / and % on f32 in here
+pub fn step_ride(
+    player: &mut Player,
+    obstacles: &mut ObstaclePool,
+    trees: &mut ObstaclePool,
+    rng: &mut Rng,
+    resolution: Vec2,
+    round_time: f32,
+    distance: &mut f32,
+) {
+    player.step(round_time);
+    obstacles.step(player.speed);
+    trees.step(player.speed * 0.7);
+    obstacles.spawn_attempt(resolution, round_time, rng);
+    trees.spawn_attempt(resolution, round_time, rng);
+    *distance += player.speed;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outcome {
+    pub distance: f32,
+    pub time: f32,
+    pub crashed: bool,
+}
+
+// the ride without a window, audio or menus, for checking replays and racing side by side.
+// sound events pile up on the player for whoever wants to play them
+#[derive(Clone)]
+pub struct Simulation {
+    pub player: Player,
+    pub obstacles: ObstaclePool,
+    pub trees: ObstaclePool,
+    pub rng: Rng,
+    pub resolution: Vec2,
+    pub distance: f32,
+    pub round_time: f32,
+    pub crashed: bool,
+    // what the run has done towards the achievements
+    pub stats: RunStats,
+}
+
+impl Simulation {
+    pub fn new(seed: u64, difficulty: Difficulty, stamina_mode: bool) -> Self {
+        let resolution = vec2(RESOLUTION_X, RESOLUTION_Y);
+        let mut player = Player::new(vec2(128., 128.), resolution);
+        player.stamina_mode = stamina_mode;
+        player.reset();
+        Self {
+            player,
+            obstacles: ObstaclePool::new(10, Some(difficulty.obstacle_settings())),
+            trees: ObstaclePool::new(100, Some(ObstaclePoolSettings::trees())),
+            rng: Rng::new(seed),
+            resolution,
+            distance: 0.,
+            round_time: 0.,
+            crashed: false,
+            stats: RunStats::default(),
+        }
+    }
+
+    // rides every frame of the replay, frames after a crash are ignored like the game does
+    pub fn replay(replay: &Replay) -> Outcome {
+        let mut sim = Self::new(replay.seed, replay.difficulty, replay.stamina_mode);
+        for frame in &replay.frames {
+            if sim.crashed {
+                break;
+            }
+            sim.tick(&frame.input, frame.delta_time);
+            for _ in 0..frame.steps {
+                if sim.step() {
+                    break;
+                }
+            }
+            // nobody listens to a replay
+            sim.player.sound_events.clear();
+        }
+        sim.outcome()
+    }
+
+    pub fn tick(&mut self, input: &PlayerInput, delta_time: f32) {
+        self.player.tick(input);
+        self.round_time += delta_time;
+    }
+
+    // true once the rider has crashed
+    pub fn step(&mut self) -> bool {
+        if self.crashed {
+            return true;
+        }
+        step_ride(
+            &mut self.player,
+            &mut self.obstacles,
+            &mut self.trees,
+            &mut self.rng,
+            self.resolution,
+            self.round_time,
+            &mut self.distance,
+        );
+        self.stats
+            .observe(&self.player, self.distance, self.round_time);
+        self.crashed = self.obstacles.has_collision(&[&self.player]).is_some();
+        if self.crashed {
+            self.stats.crash();
+        }
+        self.crashed
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        Outcome {
+            distance: self.distance,
+            time: self.round_time,
+            crashed: self.crashed,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{
+    achievements::RunStats, game::Game, obstacles::ObstaclePoolState, player::PlayerState,
+    rng::Rng, storage,
+};
+use serde::{Deserialize, Serialize};
+use std::io;
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub player: PlayerState,
+    pub obstacles: ObstaclePoolState,
+    pub trees: ObstaclePoolState,
+    pub distance: f32,
+    pub round_time: f32,
+    pub world_time: f32,
+    pub day_night_cycle_time: f32,
+    pub rng_state: u64,
+    // so jumps ridden again after a rewind aren't counted twice
+    #[serde(default)]
+    pub run_stats: RunStats,
+    // snapshots from before these were kept leave the game's own in place
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub next_checkpoint: Option<f32>,
+}
+
+impl Snapshot {
+    pub fn capture(game: &Game) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            player: game.player.state(),
+            obstacles: game.obstacles.state(),
+            trees: game.trees.state(),
+            distance: game.distance,
+            round_time: game.round_time,
+            world_time: game.world_time,
+            day_night_cycle_time: game.day_night_cycle_time,
+            rng_state: game.rng.state(),
+            run_stats: game.run_stats,
+            seed: Some(game.seed),
+            next_checkpoint: Some(game.next_checkpoint),
+        }
+    }
+
+    pub fn restore(&self, game: &mut Game) {
+        game.player.restore(&self.player);
+        game.obstacles.restore(&self.obstacles);
+        game.trees.restore(&self.trees);
+        game.distance = self.distance;
+        game.round_time = self.round_time;
+        game.world_time = self.world_time;
+        game.day_night_cycle_time = self.day_night_cycle_time;
+        game.rng = Rng::from_state(self.rng_state);
+        game.run_stats = self.run_stats;
+        // a quick load can come from another trail
+        if let Some(seed) = self.seed.filter(|seed| *seed != game.seed) {
+            game.seed = seed;
+            game.background.reseed(seed);
+        }
+        if let Some(next_checkpoint) = self.next_checkpoint {
+            game.next_checkpoint = next_checkpoint;
+        }
+        game.ghosts.rewind_to(self.round_time);
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        let snapshot: Snapshot = serde_json::from_str(json)?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported snapshot version {}", snapshot.version),
+            ));
+        }
+        Ok(snapshot)
+    }
+
+    pub fn save(&self, slot: &str) -> io::Result<()> {
+        storage::save(&key(slot), &self.to_json())
+    }
+
+    pub fn load(slot: &str) -> io::Result<Self> {
+        match storage::load(&key(slot)) {
+            Some(json) => Self::from_json(&json),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no snapshot in slot {}", slot),
+            )),
+        }
+    }
+
+    pub fn remove(slot: &str) {
+        storage::remove(&key(slot));
+    }
+}
+
+fn key(slot: &str) -> String {
+    format!("snapshot-{}", slot)
+}
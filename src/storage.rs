@@ -0,0 +1,198 @@
+use std::io;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::{
+        env, fs, io,
+        path::PathBuf,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    static IMPORT_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+    fn data_dir() -> PathBuf {
+        if let Ok(dir) = env::var("MTB_DATA_DIR") {
+            return PathBuf::from(dir);
+        }
+        if let Ok(dir) = env::var("APPDATA") {
+            return PathBuf::from(dir).join("mtb");
+        }
+        if let Ok(dir) = env::var("XDG_DATA_HOME") {
+            return PathBuf::from(dir).join("mtb");
+        }
+        if let Ok(dir) = env::var("HOME") {
+            return PathBuf::from(dir).join(".local/share/mtb");
+        }
+        PathBuf::from(".")
+    }
+
+    fn path(key: &str) -> PathBuf {
+        data_dir().join(format!("{}.json", key))
+    }
+
+    pub fn save(key: &str, data: &str) -> io::Result<()> {
+        fs::create_dir_all(data_dir())?;
+        fs::write(path(key), data)
+    }
+
+    pub fn load(key: &str) -> Option<String> {
+        fs::read_to_string(path(key)).ok()
+    }
+
+    pub fn remove(key: &str) {
+        let _ = fs::remove_file(path(key));
+    }
+
+    pub fn export(file_name: &str, data: &str) -> io::Result<String> {
+        let dir = data_dir().join("exports");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(file_name);
+        fs::write(&path, data)?;
+        Ok(path.display().to_string())
+    }
+
+    // there's no file picker on desktop, files are picked up from the imports folder instead
+    pub fn request_import() {
+        IMPORT_REQUESTED.store(true, Ordering::Relaxed);
+    }
+
+    pub fn take_imports() -> Vec<String> {
+        if !IMPORT_REQUESTED.swap(false, Ordering::Relaxed) {
+            return Vec::new();
+        }
+        let dir = data_dir().join("imports");
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => {
+                macroquad::logging::warn!("nothing to import in {}", dir.display());
+                return Vec::new();
+            }
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .collect()
+    }
+}
+
+// backed by localStorage through wasm/mtb_plugin.js
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use std::io;
+
+    extern "C" {
+        fn mtb_storage_set(key: *const u8, key_len: u32, data: *const u8, data_len: u32) -> u32;
+        fn mtb_storage_len(key: *const u8, key_len: u32) -> i32;
+        fn mtb_storage_get(key: *const u8, key_len: u32, buffer: *mut u8, buffer_len: u32);
+        fn mtb_storage_remove(key: *const u8, key_len: u32);
+        fn mtb_export(name: *const u8, name_len: u32, data: *const u8, data_len: u32);
+        fn mtb_request_import();
+        fn mtb_import_len() -> i32;
+        fn mtb_import_take(buffer: *mut u8, buffer_len: u32);
+    }
+
+    pub fn save(key: &str, data: &str) -> io::Result<()> {
+        let stored = unsafe {
+            mtb_storage_set(
+                key.as_ptr(),
+                key.len() as u32,
+                data.as_ptr(),
+                data.len() as u32,
+            )
+        };
+        if stored == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "localStorage is unavailable or full",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn load(key: &str) -> Option<String> {
+        let len = unsafe { mtb_storage_len(key.as_ptr(), key.len() as u32) };
+        if len < 0 {
+            return None;
+        }
+        let mut buffer = vec![0u8; len as usize];
+        unsafe {
+            mtb_storage_get(
+                key.as_ptr(),
+                key.len() as u32,
+                buffer.as_mut_ptr(),
+                buffer.len() as u32,
+            )
+        };
+        String::from_utf8(buffer).ok()
+    }
+
+    pub fn remove(key: &str) {
+        unsafe { mtb_storage_remove(key.as_ptr(), key.len() as u32) };
+    }
+
+    // downloaded by the browser
+    pub fn export(file_name: &str, data: &str) -> io::Result<String> {
+        unsafe {
+            mtb_export(
+                file_name.as_ptr(),
+                file_name.len() as u32,
+                data.as_ptr(),
+                data.len() as u32,
+            )
+        };
+        Ok(file_name.to_string())
+    }
+
+    // opens a file picker, whatever gets picked arrives through take_imports later
+    pub fn request_import() {
+        unsafe { mtb_request_import() };
+    }
+
+    pub fn take_imports() -> Vec<String> {
+        let mut imports = Vec::new();
+        loop {
+            let len = unsafe { mtb_import_len() };
+            if len < 0 {
+                return imports;
+            }
+            let mut buffer = vec![0u8; len as usize];
+            unsafe { mtb_import_take(buffer.as_mut_ptr(), buffer.len() as u32) };
+            if let Ok(data) = String::from_utf8(buffer) {
+                imports.push(data);
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+use native as backend;
+#[cfg(target_arch = "wasm32")]
+use web as backend;
+
+pub fn save(key: &str, data: &str) -> io::Result<()> {
+    backend::save(key, data)
+}
+
+pub fn load(key: &str) -> Option<String> {
+    backend::load(key)
+}
+
+pub fn remove(key: &str) {
+    backend::remove(key)
+}
+
+// hands a file to the player to share, returns where it went
+pub fn export(file_name: &str, data: &str) -> io::Result<String> {
+    backend::export(file_name, data)
+}
+
+pub fn request_import() {
+    backend::request_import()
+}
+
+// files the player picked to import since the last call
+pub fn take_imports() -> Vec<String> {
+    backend::take_imports()
+}
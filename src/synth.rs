@@ -0,0 +1,444 @@
+use crate::rng::Rng;
+use std::f32::consts::TAU;
+
+pub const SAMPLE_RATE: u32 = 22050;
+
+fn sample_count(seconds: f32) -> usize {
+    (seconds * SAMPLE_RATE as f32) as usize
+}
+
+fn white(rng: &mut Rng) -> f32 {
+    rng.gen_f32(-1., 1.)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LowPass {
+    coefficient: f32,
+    value: f32,
+}
+
+impl LowPass {
+    fn new(cutoff: f32) -> Self {
+        Self {
+            coefficient: 1. - (-TAU * cutoff / SAMPLE_RATE as f32).exp(),
+            value: 0.,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        self.value += self.coefficient * (input - self.value);
+        self.value
+    }
+}
+
+// crossfades the tail into the head so the buffer loops without a click
+pub fn make_loopable(samples: &mut Vec<f32>, fade: usize) {
+    let fade = fade.min(samples.len() / 2);
+    let len = samples.len();
+    for i in 0..fade {
+        let t = i as f32 / fade as f32;
+        samples[i] = samples[i] * t + samples[len - fade + i] * (1. - t);
+    }
+    samples.truncate(len - fade);
+}
+
+// knobbly tread on gravel: filtered noise that gets brighter, louder and bumpier with speed
+pub fn tire_roll(speed: f32, seconds: f32, seed: u64) -> Vec<f32> {
+    let mut rng = Rng::new(seed);
+    let intensity = speed.clamp(0., 1.);
+    let mut filter = LowPass::new(150. + 2500. * intensity);
+    let tread_rate = 20. + 140. * intensity;
+    let mut samples: Vec<f32> = (0..sample_count(seconds))
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let tread = 0.75 + 0.25 * (TAU * tread_rate * t).sin();
+            filter.process(white(&mut rng)) * tread * (0.3 + 0.7 * intensity)
+        })
+        .collect();
+    normalize(&mut samples, 0.2 + 0.5 * intensity);
+    make_loopable(&mut samples, sample_count(0.05));
+    samples
+}
+
+pub fn wind(seconds: f32, seed: u64) -> Vec<f32> {
+    let mut rng = Rng::new(seed);
+    let mut low = LowPass::new(900.);
+    let mut lower = LowPass::new(300.);
+    let mut samples: Vec<f32> = (0..sample_count(seconds))
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let gust = 0.6 + 0.4 * (TAU * 0.7 * t).sin() * (TAU * 0.23 * t).sin();
+            let noise = white(&mut rng);
+            (low.process(noise) - lower.process(noise)) * gust
+        })
+        .collect();
+    normalize(&mut samples, 0.6);
+    make_loopable(&mut samples, sample_count(0.2));
+    samples
+}
+
+fn click(samples: &mut [f32], at: usize, length: usize, amplitude: f32, rng: &mut Rng) {
+    let mut filter = LowPass::new(3000.);
+    for i in 0..length {
+        if let Some(sample) = samples.get_mut(at + i) {
+            let decay = (-(i as f32) / (length as f32 * 0.2)).exp();
+            let noise = white(rng);
+            *sample += (noise - filter.process(noise)) * decay * amplitude;
+        }
+    }
+}
+
+// the ratcheting hub when coasting
+pub fn freewheel(clicks_per_second: f32, seconds: f32, seed: u64) -> Vec<f32> {
+    let mut rng = Rng::new(seed);
+    let mut samples = vec![0.; sample_count(seconds)];
+    let interval = (SAMPLE_RATE as f32 / clicks_per_second) as usize;
+    let mut at = 0;
+    while at < samples.len() {
+        click(&mut samples, at, sample_count(0.004), 0.8, &mut rng);
+        at += interval;
+    }
+    normalize(&mut samples, 0.5);
+    samples
+}
+
+// one link of chain going over the chainring
+pub fn chain_tick(seed: u64) -> Vec<f32> {
+    let mut rng = Rng::new(seed);
+    let length = sample_count(0.05);
+    let mut samples = vec![0.; length];
+    click(&mut samples, 0, length, 0.5, &mut rng);
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let t = i as f32 / SAMPLE_RATE as f32;
+        let decay = (-t * 90.).exp();
+        *sample += ((TAU * 2350. * t).sin() + 0.5 * (TAU * 3710. * t).sin()) * decay * 0.3;
+    }
+    normalize(&mut samples, 0.4);
+    samples
+}
+
+// a thump that drops in pitch, heavier landings are longer, lower and crunchier
+pub fn landing(intensity: f32, seed: u64) -> Vec<f32> {
+    let mut rng = Rng::new(seed);
+    let intensity = intensity.clamp(0., 1.);
+    let length = sample_count(0.08 + 0.22 * intensity);
+    let mut filter = LowPass::new(600. + 1400. * intensity);
+    let mut phase = 0.;
+    let mut samples: Vec<f32> = (0..length)
+        .map(|i| {
+            let progress = i as f32 / length as f32;
+            let frequency = (140. - 60. * intensity) * (1. - 0.6 * progress);
+            phase += TAU * frequency / SAMPLE_RATE as f32;
+            let decay = (1. - progress).powi(2);
+            let body = phase.sin();
+            let crunch = filter.process(white(&mut rng)) * intensity;
+            (body + crunch) * decay
+        })
+        .collect();
+    normalize(&mut samples, 0.4 + 0.6 * intensity);
+    samples
+}
+
+// nudged so a whole number of cycles fits the loop, which then wraps around without a click
+fn looped(frequency: f32, seconds: f32) -> f32 {
+    (frequency * seconds).round() / seconds
+}
+
+fn kick(samples: &mut [f32], at: usize, pitch: f32) {
+    let length = sample_count(0.15);
+    let mut phase = 0.;
+    for i in 0..length {
+        if let Some(sample) = samples.get_mut(at + i) {
+            let progress = i as f32 / length as f32;
+            phase += TAU * (110. - 70. * progress) * pitch / SAMPLE_RATE as f32;
+            *sample += phase.sin() * (1. - progress).powi(2);
+        }
+    }
+}
+
+// a kick on every beat and a hat between them, the bars spread over the whole length. twice the
+// bars in the same time is twice the tempo, so the calm and the hurried takes stay in step
+pub fn drums(bars: usize, seconds: f32, pitch: f32, seed: u64) -> Vec<f32> {
+    let mut rng = Rng::new(seed);
+    let beats = 4 * bars;
+    let length = sample_count(seconds);
+    let mut samples = vec![0.; length];
+    // beats are placed from the whole length, rounding each one's length would drift
+    let at = |beat: f32| (beat * length as f32 / beats as f32) as usize;
+    for i in 0..beats {
+        kick(&mut samples, at(i as f32), pitch);
+        click(
+            &mut samples,
+            at(i as f32 + 0.5),
+            sample_count(0.03),
+            0.6,
+            &mut rng,
+        );
+    }
+    normalize(&mut samples, 0.5);
+    samples
+}
+
+// a minor chord swelling slowly, for the night
+pub fn pad(seconds: f32) -> Vec<f32> {
+    let chord = [220., 261.63, 329.63].map(|frequency| looped(frequency, seconds));
+    let swell = looped(0.25, seconds);
+    let mut samples: Vec<f32> = (0..sample_count(seconds))
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let level = 0.6 + 0.4 * (TAU * swell * t).sin();
+            let tone: f32 = chord
+                .iter()
+                .map(|frequency| {
+                    (TAU * frequency * t).sin() + 0.3 * (TAU * 2. * frequency * t).sin()
+                })
+                .sum();
+            tone * level
+        })
+        .collect();
+    normalize(&mut samples, 0.4);
+    samples
+}
+
+// the chord falling away under a burst of noise when the rider crashes
+pub fn stinger(seed: u64) -> Vec<f32> {
+    let mut rng = Rng::new(seed);
+    let length = sample_count(1.2);
+    let mut filter = LowPass::new(1200.);
+    let mut phases = [0f32; 3];
+    let mut samples: Vec<f32> = (0..length)
+        .map(|i| {
+            let progress = i as f32 / length as f32;
+            let fall = 1. - 0.5 * progress;
+            let mut tone = 0.;
+            for (phase, frequency) in phases.iter_mut().zip([440., 523.25, 659.25]) {
+                *phase += TAU * frequency * fall / SAMPLE_RATE as f32;
+                tone += phase.sin();
+            }
+            let noise = filter.process(white(&mut rng)) * (1. - progress * 8.).max(0.);
+            (tone + noise * 3.) * (1. - progress).powi(2)
+        })
+        .collect();
+    normalize(&mut samples, 0.6);
+    samples
+}
+
+pub fn normalize(samples: &mut [f32], peak: f32) {
+    let max = samples.iter().fold(0f32, |max, s| max.max(s.abs()));
+    if max > 0. {
+        for sample in samples.iter_mut() {
+            *sample *= peak / max;
+        }
+    }
+}
+
+// mono 16 bit PCM so macroquad can load it like any other sound file
+pub fn to_wav(samples: &[f32]) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVE");
+    bytes.extend_from_slice(b"fmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let value = (sample.clamp(-1., 1.) * i16::MAX as f32) as i16;
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+// how long a wav file plays, from its byte rate and the size of its samples
+pub fn wav_seconds(bytes: &[u8]) -> Option<f32> {
+    let u32_at = |at: usize| {
+        bytes
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WAVE" {
+        return None;
+    }
+    let mut byte_rate = None;
+    let mut at = 12;
+    while let (Some(id), Some(size)) = (bytes.get(at..at + 4), u32_at(at + 4)) {
+        match id {
+            b"fmt " => byte_rate = u32_at(at + 16),
+            b"data" => {
+                return byte_rate
+                    .filter(|rate| *rate > 0)
+                    .map(|rate| size as f32 / rate as f32)
+            }
+            _ => {}
+        }
+        // chunks are padded to an even length
+        at += 8 + size as usize + size as usize % 2;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0f32, |max, s| max.max(s.abs()))
+    }
+
+    fn assert_peak(samples: &[f32], expected: f32) {
+        assert!(
+            (peak(samples) - expected).abs() < 1e-4,
+            "expected a peak of {}, got {}",
+            expected,
+            peak(samples)
+        );
+    }
+
+    // the loudest sample in the last few milliseconds
+    fn tail_peak(samples: &[f32]) -> f32 {
+        peak(&samples[samples.len() - sample_count(0.005)..])
+    }
+
+    #[test]
+    fn buffers_last_as_long_as_asked() {
+        assert_eq!(freewheel(12., 1., 1).len(), SAMPLE_RATE as usize);
+        assert_eq!(chain_tick(1).len(), sample_count(0.05));
+        assert_eq!(landing(0., 1).len(), sample_count(0.08));
+        assert_eq!(landing(1., 1).len(), sample_count(0.3));
+        // loops lose the crossfaded tail
+        assert_eq!(
+            tire_roll(0.5, 1., 1).len(),
+            SAMPLE_RATE as usize - sample_count(0.05)
+        );
+        assert_eq!(
+            wind(2., 1).len(),
+            2 * SAMPLE_RATE as usize - sample_count(0.2)
+        );
+    }
+
+    #[test]
+    fn buffers_are_normalized_to_their_peak() {
+        assert_peak(&freewheel(12., 1., 1), 0.5);
+        assert_peak(&chain_tick(1), 0.4);
+        assert_peak(&landing(0.5, 1), 0.7);
+    }
+
+    #[test]
+    fn loops_stay_under_their_peak() {
+        // the crossfade comes after normalizing, it can only take the edge off
+        for (samples, expected) in [
+            (tire_roll(0., 1., 1), 0.2),
+            (tire_roll(1., 1., 1), 0.7),
+            // faster than the top speed sounds like the top speed
+            (tire_roll(3., 1., 1), 0.7),
+            (wind(1., 1), 0.6),
+        ] {
+            assert!(peak(&samples) <= expected + 1e-4);
+            assert!(peak(&samples) > expected * 0.8);
+        }
+    }
+
+    #[test]
+    fn one_shots_fade_to_silence() {
+        assert!(tail_peak(&chain_tick(1)) < 0.01);
+        assert!(tail_peak(&landing(0., 1)) < 0.01);
+        assert!(tail_peak(&landing(1., 1)) < 0.01);
+    }
+
+    #[test]
+    fn the_same_seed_makes_the_same_sound() {
+        assert_eq!(tire_roll(0.5, 0.5, 7), tire_roll(0.5, 0.5, 7));
+        assert_ne!(tire_roll(0.5, 0.5, 7), tire_roll(0.5, 0.5, 8));
+    }
+
+    #[test]
+    fn stems_last_whole_bars() {
+        assert_eq!(drums(4, 8., 1., 1).len(), sample_count(8.));
+        // the hurried take keeps in step with the calm one
+        assert_eq!(drums(8, 8., 1.5, 1).len(), drums(4, 8., 1., 1).len());
+        assert_eq!(pad(8.).len(), sample_count(8.));
+        assert_peak(&drums(4, 8., 1., 1), 0.5);
+        assert_peak(&pad(8.), 0.4);
+        assert_peak(&stinger(1), 0.6);
+    }
+
+    #[test]
+    fn wav_files_are_measured_from_their_header() {
+        assert_eq!(wav_seconds(&to_wav(&pad(8.))), Some(8.));
+        // a chunk before the samples is skipped, a 44.1khz stereo header reads the same
+        let mut bytes = to_wav(&[0.; 4]);
+        bytes.splice(36..36, b"LIST\x03\0\0\0abc\0".iter().copied());
+        bytes[24..28].copy_from_slice(&44_100u32.to_le_bytes());
+        bytes[28..32].copy_from_slice(&176_400u32.to_le_bytes());
+        bytes[52..56].copy_from_slice(&(176_400u32 * 3).to_le_bytes());
+        assert_eq!(wav_seconds(&bytes), Some(3.));
+        assert_eq!(wav_seconds(b"OggS"), None);
+        assert_eq!(wav_seconds(&to_wav(&[])[..36]), None);
+    }
+
+    #[test]
+    fn the_pad_wraps_around_without_a_click() {
+        let samples = pad(8.);
+        let step = samples
+            .iter()
+            .zip(&samples[1..])
+            .fold(0f32, |max, (a, b)| max.max((b - a).abs()));
+        let wrap = (samples[0] - samples[samples.len() - 1]).abs();
+        assert!(wrap <= step * 1.5, "wraps by {}, steps by {}", wrap, step);
+    }
+
+    #[test]
+    fn the_stinger_fades_to_silence() {
+        assert!(tail_peak(&stinger(1)) < 0.01);
+    }
+
+    #[test]
+    fn loopable_buffers_start_where_they_ended() {
+        let mut samples: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        make_loopable(&mut samples, 10);
+        assert_eq!(samples.len(), 90);
+        // the head starts on the old tail and fades into itself
+        assert_eq!(samples[0], 90.);
+        assert_eq!(samples[89], 89.);
+    }
+
+    #[test]
+    fn silence_stays_silent() {
+        let mut samples = vec![0.; 10];
+        normalize(&mut samples, 0.5);
+        assert!(samples.iter().all(|s| *s == 0.));
+    }
+
+    #[test]
+    fn wav_header_describes_mono_16_bit_pcm() {
+        let samples = [0., 1., -1., 2.];
+        let wav = to_wav(&samples);
+        let u16_at = |at: usize| u16::from_le_bytes([wav[at], wav[at + 1]]);
+        let u32_at =
+            |at: usize| u32::from_le_bytes([wav[at], wav[at + 1], wav[at + 2], wav[at + 3]]);
+        assert_eq!(wav.len(), 44 + samples.len() * 2);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32_at(4), wav.len() as u32 - 8);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u16_at(20), 1);
+        assert_eq!(u16_at(22), 1);
+        assert_eq!(u32_at(24), SAMPLE_RATE);
+        assert_eq!(u32_at(28), SAMPLE_RATE * 2);
+        assert_eq!(u16_at(34), 16);
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(u32_at(40), samples.len() as u32 * 2);
+        // samples past full scale are clipped
+        let data: Vec<i16> = wav[44..]
+            .chunks(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        assert_eq!(data, vec![0, i16::MAX, -i16::MAX, i16::MAX]);
+    }
+}
@@ -0,0 +1,414 @@
+use crate::{constants::*, viewport::Viewport};
+use macroquad::prelude::*;
+
+const WIDGET_FONT_SIZE: f32 = 40.;
+const LINE_SPACING: f32 = 1.4;
+const COLUMN_WIDTH: f32 = 360.;
+const SLIDER_WIDTH: f32 = 240.;
+const SLIDER_HEIGHT: f32 = 16.;
+const PADDING: f32 = 12.;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WidgetKind {
+    Label { font_size: f32, color: Color },
+    Button,
+    Slider { value: f32 },
+    Choice { value: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct Widget<T> {
+    pub id: Option<T>,
+    pub text: String,
+    pub kind: WidgetKind,
+    rect: Rect,
+    // shrinks text that wouldn't fit the width of the layout
+    fit: f32,
+}
+
+impl<T> Widget<T> {
+    fn is_focusable(&self) -> bool {
+        self.id.is_some() && !matches!(self.kind, WidgetKind::Label { .. })
+    }
+
+    fn font_size(&self) -> f32 {
+        let font_size = match self.kind {
+            WidgetKind::Label { font_size, .. } => font_size,
+            _ => WIDGET_FONT_SIZE,
+        };
+        font_size * self.fit
+    }
+
+    fn baseline(&self) -> f32 {
+        let font_size = self.font_size();
+        self.rect.y + (self.rect.h - font_size * LINE_SPACING) * 0.5 + font_size
+    }
+
+    fn is_two_column(&self) -> bool {
+        matches!(
+            self.kind,
+            WidgetKind::Slider { .. } | WidgetKind::Choice { .. }
+        )
+    }
+
+    // where a click lands on the slider track, from 0 to 1. none off the track, on the label
+    fn slider_value_at(&self, x: f32) -> Option<f32> {
+        let track_x = self.rect.x + self.rect.w * 0.5;
+        let value = (x - track_x) / SLIDER_WIDTH;
+        (0. ..=1.).contains(&value).then_some(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UiEvent<T> {
+    Activated(T),
+    Changed(T, i32),
+    SliderSet(T, f32),
+}
+
+// menu navigation from the keyboard and mouse or touch, macroquad 0.3 doesn't read gamepads
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UiInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub confirm: bool,
+    pub confirm_down: bool,
+    pub pointer: Vec2,
+    pub click: bool,
+    pub pointer_down: bool,
+}
+
+impl UiInput {
+    pub fn poll(viewport: &Viewport) -> Self {
+        Self {
+            up: is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W),
+            down: is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S),
+            left: is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A),
+            right: is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D),
+            confirm: is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space),
+            confirm_down: is_key_down(KeyCode::Enter) || is_key_down(KeyCode::Space),
+            pointer: viewport.mouse_position(),
+            click: is_mouse_button_pressed(MouseButton::Left),
+            pointer_down: is_mouse_button_down(MouseButton::Left),
+        }
+    }
+}
+
+// a vertical stack of widgets centered in the viewport that remembers focus between frames
+#[derive(Debug, Clone)]
+pub struct Menu<T> {
+    widgets: Vec<Widget<T>>,
+    focus: Option<usize>,
+    pressed: Option<usize>,
+    pointer: Vec2,
+    // how far a menu taller than its area is scrolled to keep the focus in view
+    scroll: f32,
+}
+
+impl<T: Copy + PartialEq> Default for Menu<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + PartialEq> Menu<T> {
+    pub fn new() -> Self {
+        Self {
+            widgets: Vec::new(),
+            focus: None,
+            pressed: None,
+            pointer: Vec2::ZERO,
+            scroll: 0.,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.widgets.clear();
+    }
+
+    pub fn reset_focus(&mut self) {
+        self.focus = None;
+        self.pressed = None;
+        self.scroll = 0.;
+    }
+
+    pub fn focused(&self) -> Option<T> {
+        self.focus
+            .and_then(|i| self.widgets.get(i))
+            .and_then(|w| w.id)
+    }
+
+    fn push(&mut self, id: Option<T>, text: &str, kind: WidgetKind) {
+        self.widgets.push(Widget {
+            id,
+            text: text.to_string(),
+            kind,
+            rect: Rect::new(0., 0., 0., 0.),
+            fit: 1.,
+        });
+    }
+
+    pub fn label(&mut self, text: &str, font_size: f32, color: Color) {
+        self.push(None, text, WidgetKind::Label { font_size, color });
+    }
+
+    pub fn button(&mut self, id: T, text: &str) {
+        self.push(Some(id), text, WidgetKind::Button);
+    }
+
+    pub fn slider(&mut self, id: T, text: &str, value: f32) {
+        self.push(Some(id), text, WidgetKind::Slider { value });
+    }
+
+    pub fn choice(&mut self, id: T, text: &str, value: &str) {
+        self.push(
+            Some(id),
+            text,
+            WidgetKind::Choice {
+                value: value.to_string(),
+            },
+        );
+    }
+
+    // touch_target is the smallest height a focusable widget gets, zero when not on a touch layout
+    fn layout(&mut self, area: Rect, touch_target: f32) {
+        let max_width = area.w - PADDING * 2.;
+        for widget in &mut self.widgets {
+            widget.fit = 1.;
+            let width = if widget.is_two_column() {
+                COLUMN_WIDTH * 2.
+            } else {
+                measure_text(&widget.text, None, widget.font_size() as u16, 1.).width
+            };
+            widget.fit = (max_width / width).min(1.);
+        }
+
+        let line_height = |widget: &Widget<T>| {
+            let height = widget.font_size() * LINE_SPACING;
+            if widget.is_focusable() {
+                height.max(touch_target)
+            } else {
+                height
+            }
+        };
+        let height: f32 = self.widgets.iter().map(line_height).sum();
+        let center_x = area.x + area.w * 0.5;
+        let overflow = (height - area.h).max(0.);
+        let mut y = area.y + (area.h - height).max(0.) * 0.5;
+        for i in 0..self.widgets.len() {
+            let widget = &self.widgets[i];
+            let font_size = widget.font_size();
+            let mut width = if widget.is_two_column() {
+                COLUMN_WIDTH * 2. * widget.fit
+            } else {
+                measure_text(&widget.text, None, font_size as u16, 1.).width + PADDING * 2.
+            };
+            if touch_target > 0. && widget.kind == WidgetKind::Button {
+                width = width.max(area.w * 0.6);
+            }
+            let height = line_height(widget);
+            self.widgets[i].rect = Rect::new(center_x - width * 0.5, y, width, height);
+            y += height;
+        }
+
+        if let Some(focused) = self.focus.and_then(|i| self.widgets.get(i)) {
+            let rect = focused.rect;
+            if rect.y - self.scroll < area.y {
+                self.scroll = rect.y - area.y;
+            } else if rect.bottom() - self.scroll > area.bottom() {
+                self.scroll = rect.bottom() - area.bottom();
+            }
+        }
+        self.scroll = self.scroll.clamp(0., overflow);
+        for widget in &mut self.widgets {
+            widget.rect.y -= self.scroll;
+        }
+    }
+
+    fn step_focus(&mut self, direction: i32) {
+        let count = self.widgets.len() as i32;
+        let mut i = self.focus.map(|i| i as i32).unwrap_or(-direction.max(0));
+        for _ in 0..count {
+            i = (i + direction).rem_euclid(count);
+            if self.widgets[i as usize].is_focusable() {
+                self.focus = Some(i as usize);
+                return;
+            }
+        }
+    }
+
+    fn hovered(&self, pointer: Vec2) -> Option<usize> {
+        self.widgets
+            .iter()
+            .position(|w| w.is_focusable() && w.rect.contains(pointer))
+    }
+
+    pub fn tick(&mut self, viewport: &Viewport) -> Option<UiEvent<T>> {
+        self.tick_with(
+            &UiInput::poll(viewport),
+            viewport.safe_area(),
+            viewport.touch_target(),
+        )
+    }
+
+    // lays out inside the given area, the pointer has to be in the same space
+    pub fn tick_with(
+        &mut self,
+        input: &UiInput,
+        area: Rect,
+        touch_target: f32,
+    ) -> Option<UiEvent<T>> {
+        self.layout(area, touch_target);
+        if self.widgets.is_empty() {
+            return None;
+        }
+
+        let focus_is_valid = self
+            .focus
+            .is_some_and(|i| i < self.widgets.len() && self.widgets[i].is_focusable());
+        if !focus_is_valid {
+            self.focus = None;
+            self.step_focus(1);
+        }
+
+        if input.up {
+            self.step_focus(-1);
+        }
+        if input.down {
+            self.step_focus(1);
+        }
+
+        let hovered = self.hovered(input.pointer);
+        if input.pointer != self.pointer && hovered.is_some() {
+            self.focus = hovered;
+        }
+        self.pointer = input.pointer;
+
+        self.pressed = if input.pointer_down {
+            hovered
+        } else if input.confirm_down {
+            self.focus
+        } else {
+            None
+        };
+
+        if input.click {
+            if let Some(i) = hovered {
+                let widget = &self.widgets[i];
+                let id = widget.id?;
+                return match widget.kind {
+                    WidgetKind::Button => Some(UiEvent::Activated(id)),
+                    WidgetKind::Choice { .. } => Some(UiEvent::Changed(id, 1)),
+                    // clicking the label only focuses the slider
+                    WidgetKind::Slider { .. } => widget
+                        .slider_value_at(input.pointer.x)
+                        .map(|value| UiEvent::SliderSet(id, value)),
+                    WidgetKind::Label { .. } => None,
+                };
+            }
+        }
+
+        let widget = &self.widgets[self.focus?];
+        let id = widget.id?;
+        match widget.kind {
+            WidgetKind::Button if input.confirm => Some(UiEvent::Activated(id)),
+            WidgetKind::Choice { .. } if input.confirm || input.right => {
+                Some(UiEvent::Changed(id, 1))
+            }
+            WidgetKind::Choice { .. } | WidgetKind::Slider { .. } if input.left => {
+                Some(UiEvent::Changed(id, -1))
+            }
+            WidgetKind::Slider { .. } if input.right => Some(UiEvent::Changed(id, 1)),
+            _ => None,
+        }
+    }
+
+    fn color(&self, i: usize) -> Color {
+        if self.pressed == Some(i) {
+            PALETTE[8]
+        } else if self.focus == Some(i) {
+            PALETTE[14]
+        } else {
+            PALETTE[12]
+        }
+    }
+
+    pub fn render(&self) {
+        for (i, widget) in self.widgets.iter().enumerate() {
+            let rect = widget.rect;
+            let font_size = widget.font_size();
+            let baseline = widget.baseline();
+            let color = self.color(i);
+            let value_x = rect.x + rect.w * 0.5;
+            match &widget.kind {
+                WidgetKind::Label { color, .. } => {
+                    draw_text(&widget.text, rect.x + PADDING, baseline, font_size, *color);
+                }
+                WidgetKind::Button => {
+                    if self.focus == Some(i) {
+                        draw_rectangle(rect.x, rect.y, rect.w, rect.h, PALETTE[0]);
+                        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 4., color);
+                    }
+                    let text_width = measure_text(&widget.text, None, font_size as u16, 1.).width;
+                    let x = rect.x + (rect.w - text_width) * 0.5;
+                    draw_text(&widget.text, x, baseline, font_size, color);
+                }
+                WidgetKind::Choice { value } => {
+                    draw_text(&widget.text, rect.x, baseline, font_size, color);
+                    draw_text(value, value_x, baseline, font_size, color);
+                }
+                WidgetKind::Slider { value } => {
+                    draw_text(&widget.text, rect.x, baseline, font_size, color);
+                    let bar_y = baseline - SLIDER_HEIGHT;
+                    draw_rectangle(value_x, bar_y, SLIDER_WIDTH, SLIDER_HEIGHT, PALETTE[0]);
+                    draw_rectangle(value_x, bar_y, SLIDER_WIDTH * value, SLIDER_HEIGHT, color);
+                    draw_text(
+                        &format!("{}%", (value * 100.).round() as i32),
+                        value_x + SLIDER_WIDTH + PADDING,
+                        baseline,
+                        font_size,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slider() -> Widget<()> {
+        Widget {
+            id: Some(()),
+            text: "MUSIC".to_string(),
+            kind: WidgetKind::Slider { value: 0.5 },
+            rect: Rect::new(100., 0., COLUMN_WIDTH * 2., 56.),
+            fit: 1.,
+        }
+    }
+
+    #[test]
+    fn clicks_on_the_track_set_the_slider() {
+        let track_x = 100. + COLUMN_WIDTH;
+        assert_eq!(slider().slider_value_at(track_x), Some(0.));
+        assert_eq!(
+            slider().slider_value_at(track_x + SLIDER_WIDTH * 0.25),
+            Some(0.25)
+        );
+        assert_eq!(slider().slider_value_at(track_x + SLIDER_WIDTH), Some(1.));
+    }
+
+    #[test]
+    fn clicks_on_the_label_leave_the_slider_alone() {
+        assert_eq!(slider().slider_value_at(110.), None);
+        assert_eq!(slider().slider_value_at(100. + COLUMN_WIDTH - 1.), None);
+        assert_eq!(
+            slider().slider_value_at(100. + COLUMN_WIDTH + SLIDER_WIDTH + 1.),
+            None
+        );
+    }
+}
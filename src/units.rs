@@ -0,0 +1,143 @@
+use crate::{constants::*, locale::Locale};
+use serde::{Deserialize, Serialize};
+
+const KILOMETERS_PER_HOUR: f32 = 3.6;
+const MILES_PER_HOUR: f32 = 2.236_936;
+const FEET_PER_METER: f32 = 3.280_84;
+
+// the world is measured in meters and player speed in meters per tick, everything shown goes through here
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Units {
+    Metric,
+    Imperial,
+}
+
+impl Units {
+    pub fn key(&self) -> &'static str {
+        match self {
+            Units::Metric => "units.metric",
+            Units::Imperial => "units.imperial",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Units::Metric => Units::Imperial,
+            Units::Imperial => Units::Metric,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        self.next()
+    }
+
+    // km/h or mph
+    pub fn speed(&self, speed: f32) -> f32 {
+        let meters_per_second = speed * TPS;
+        match self {
+            Units::Metric => meters_per_second * KILOMETERS_PER_HOUR,
+            Units::Imperial => meters_per_second * MILES_PER_HOUR,
+        }
+    }
+
+    // meters or feet
+    pub fn distance(&self, meters: f32) -> f32 {
+        match self {
+            Units::Metric => meters,
+            Units::Imperial => meters * FEET_PER_METER,
+        }
+    }
+
+    // trail signposts land on round numbers in the units they show
+    pub fn signpost_interval(&self) -> f32 {
+        match self {
+            Units::Metric => 100.,
+            Units::Imperial => 500. / FEET_PER_METER,
+        }
+    }
+
+    // where the speedometer runs out, in the units it shows
+    pub fn gauge_max(&self) -> f32 {
+        match self {
+            Units::Metric => 60.,
+            Units::Imperial => 40.,
+        }
+    }
+
+    pub fn speed_unit(&self) -> &'static str {
+        match self {
+            Units::Metric => "units.kmh",
+            Units::Imperial => "units.mph",
+        }
+    }
+
+    pub fn format_speed(&self, locale: &Locale, speed: f32) -> String {
+        format!(
+            "{} {}",
+            locale.number(self.speed(speed).round() as f64, 0),
+            locale.text(self.speed_unit())
+        )
+    }
+
+    // short form for the hud, like 1,250m
+    pub fn format_distance(&self, locale: &Locale, meters: f32) -> String {
+        let unit = match self {
+            Units::Metric => "units.m",
+            Units::Imperial => "units.ft",
+        };
+        format!(
+            "{}{}",
+            locale.number(self.distance(meters).round() as f64, 0),
+            locale.text(unit)
+        )
+    }
+
+    // spelled out for sentences, like 1,250 meters
+    pub fn describe_distance(&self, locale: &Locale, meters: f32) -> String {
+        let key = match self {
+            Units::Metric => "units.meters",
+            Units::Imperial => "units.feet",
+        };
+        locale.plural(key, self.distance(meters).round() as i64, &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn speed_is_shown_in_kmh_and_mph() {
+        // a quarter meter a tick is 15 m/s
+        assert_close(Units::Metric.speed(0.25), 54.);
+        assert_close(Units::Imperial.speed(0.25), 33.554);
+        assert_close(Units::Metric.speed(0.), 0.);
+    }
+
+    #[test]
+    fn distance_is_shown_in_meters_and_feet() {
+        assert_close(Units::Metric.distance(100.), 100.);
+        assert_close(Units::Imperial.distance(100.), 328.084);
+        assert_close(Units::Imperial.distance(1609.344), 5280.);
+    }
+
+    #[test]
+    fn signposts_land_on_round_numbers() {
+        assert_close(Units::Metric.signpost_interval(), 100.);
+        // every 500 feet
+        assert_close(Units::Imperial.signpost_interval(), 152.4);
+        assert_close(
+            Units::Imperial.distance(Units::Imperial.signpost_interval()),
+            500.,
+        );
+    }
+}
@@ -1,12 +1,21 @@
-use macroquad::{prelude::*, rand::gen_range};
+use crate::rng::Rng;
+use macroquad::prelude::*;
 
-pub fn rgba_texture(width: u16, height: u16) -> Texture2D {
+pub fn noise_image(width: u16, height: u16, rng: &mut Rng) -> Image {
     let n = width as usize * height as usize * 4;
     let mut bytes: Vec<u8> = Vec::with_capacity(n);
     for _ in 0..n {
-        bytes.push(gen_range(0, 255));
+        bytes.push(rng.gen_i32(0, 255) as u8);
     }
-    let texture = Texture2D::from_rgba8(width, height, &bytes);
+    Image {
+        bytes,
+        width,
+        height,
+    }
+}
+
+pub fn rgba_texture(width: u16, height: u16, rng: &mut Rng) -> Texture2D {
+    let texture = Texture2D::from_image(&noise_image(width, height, rng));
     texture.set_filter(FilterMode::Nearest);
     texture
 }
@@ -0,0 +1,257 @@
+use crate::{
+    constants::*,
+    replay::{Replay, REPLAY_VERSION},
+    sim::{Outcome, Simulation},
+};
+use std::fmt;
+
+// slack for the frame clock, which is checked in f64 while the game keeps it in f32
+const CLOCK_EPSILON: f64 = 1e-4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    UnsupportedVersion(u32),
+    // a frame that's negative, not a number or long enough that the game would have skipped it
+    BadFrameTime { frame: usize },
+    // more or fewer steps than the fixed timestep allows for the frame times before them
+    StepsOutOfTime { frame: usize },
+    NoCrash,
+    // what the replay really rode to
+    Mismatch { distance: f32, time: f32 },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::UnsupportedVersion(version) => {
+                write!(f, "unsupported replay version {}", version)
+            }
+            VerifyError::BadFrameTime { frame } => {
+                write!(f, "frame {} has an impossible frame time", frame)
+            }
+            VerifyError::StepsOutOfTime { frame } => {
+                write!(f, "frame {} has steps its frame time doesn't allow", frame)
+            }
+            VerifyError::NoCrash => write!(f, "the replay doesn't end in a crash"),
+            VerifyError::Mismatch { distance, time } => write!(
+                f,
+                "the replay doesn't match the score, it rides {}m in {}s",
+                distance, time
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+// rides the replay again and checks it ends in a crash at exactly the claimed distance and time.
+// only the bits are compared: the ride is plain f32 arithmetic, which ieee 754 rounds the same way
+// on every target we build, so native and wasm runs of the same replay end on the same bits
+pub fn verify(distance: f32, time: f32, replay: &Replay) -> Result<Outcome, VerifyError> {
+    if replay.version != REPLAY_VERSION {
+        return Err(VerifyError::UnsupportedVersion(replay.version));
+    }
+    check_clock(replay)?;
+
+    let outcome = Simulation::replay(replay);
+    if !outcome.crashed {
+        return Err(VerifyError::NoCrash);
+    }
+    if outcome.distance.to_bits() != distance.to_bits() || outcome.time.to_bits() != time.to_bits()
+    {
+        return Err(VerifyError::Mismatch {
+            distance: outcome.distance,
+            time: outcome.time,
+        });
+    }
+    Ok(outcome)
+}
+
+// the game steps once for every TIMESTEP of frame time and carries the remainder over to the next
+// frame. that remainder isn't recorded, so this tracks the range it could be in and fails when no
+// remainder explains a frame's steps. extra steps would let a run cover ground without the clock
+// that spawns obstacles moving on
+fn check_clock(replay: &Replay) -> Result<(), VerifyError> {
+    let timestep = TIMESTEP as f64;
+    let (mut low, mut high) = (0., timestep);
+    let last = replay.frames.len().saturating_sub(1);
+    for (i, frame) in replay.frames.iter().enumerate() {
+        let delta_time = frame.delta_time as f64;
+        if !(delta_time > 0. && delta_time <= SUSPEND_FRAME_TIME as f64) {
+            return Err(VerifyError::BadFrameTime { frame: i });
+        }
+        // the clock kept running while paused
+        if replay.resumes.contains(&i) {
+            low = 0.;
+            high = timestep;
+        }
+        let stepped = frame.steps as f64 * timestep;
+        low += delta_time - stepped;
+        high += delta_time - stepped;
+        let too_many = high < -CLOCK_EPSILON;
+        // the crash ends the last frame early, the rest of its steps never happened
+        let too_few = low >= timestep + CLOCK_EPSILON && i != last;
+        if too_many || too_few {
+            return Err(VerifyError::StepsOutOfTime { frame: i });
+        }
+        high = high.clamp(0., timestep);
+        low = low.clamp(0., high);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{input::PlayerInput, rng::Rng, settings::Difficulty};
+
+    // rides like the game does, a frame of one timestep at a time with a jump now and then,
+    // recording every frame until the crash
+    fn ride(seed: u64) -> (Replay, Outcome) {
+        ride_on(seed, Simulation::new(seed, Difficulty::Normal, false))
+    }
+
+    fn ride_on(seed: u64, mut sim: Simulation) -> (Replay, Outcome) {
+        let mut replay = Replay::new(seed, Difficulty::Normal, false);
+        for i in 0..20_000 {
+            let input = PlayerInput {
+                jump: i % 45 == 0,
+                jump_released: i % 45 == 10,
+                ..Default::default()
+            };
+            replay.record_frame(&input, TIMESTEP);
+            sim.tick(&input, TIMESTEP);
+            replay.record_step();
+            if sim.step() {
+                break;
+            }
+        }
+        (replay, sim.outcome())
+    }
+
+    fn frames(delta_times: &[(f32, u32)]) -> Replay {
+        let mut replay = Replay::new(0, Difficulty::Normal, false);
+        for (delta_time, steps) in delta_times {
+            replay.record_frame(&PlayerInput::default(), *delta_time);
+            for _ in 0..*steps {
+                replay.record_step();
+            }
+        }
+        replay
+    }
+
+    #[test]
+    fn an_honest_run_verifies() {
+        let (replay, outcome) = ride(7);
+        assert!(outcome.crashed);
+        assert_eq!(verify(outcome.distance, outcome.time, &replay), Ok(outcome));
+    }
+
+    #[test]
+    fn a_submitted_run_verifies() {
+        // what the server gets went through json, the floats have to come out on the same bits
+        let (replay, outcome) = ride(11);
+        let json = serde_json::to_string(&replay).unwrap();
+        let submitted: Replay = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            verify(outcome.distance, outcome.time, &submitted),
+            Ok(outcome)
+        );
+    }
+
+    #[test]
+    fn a_single_bit_off_is_a_mismatch() {
+        let (replay, outcome) = ride(7);
+        let mismatch = Err(VerifyError::Mismatch {
+            distance: outcome.distance,
+            time: outcome.time,
+        });
+        let further = f32::from_bits(outcome.distance.to_bits() + 1);
+        let shorter = f32::from_bits(outcome.distance.to_bits() - 1);
+        let later = f32::from_bits(outcome.time.to_bits() + 1);
+        assert_eq!(verify(further, outcome.time, &replay), mismatch);
+        assert_eq!(verify(shorter, outcome.time, &replay), mismatch);
+        assert_eq!(verify(outcome.distance, later, &replay), mismatch);
+    }
+
+    #[test]
+    fn a_race_in_between_leaves_the_solo_trail_alone() {
+        // the solo run waits on the title screen while a race rides a trail of its own
+        let seed = 7;
+        let solo = Simulation::new(seed, Difficulty::Normal, false);
+        let mut race_rng = Rng::new(!seed);
+        let (_, race) = ride(race_rng.next_u32() as u64);
+        assert!(race.crashed);
+
+        let (replay, outcome) = ride_on(seed, solo);
+        assert_eq!(verify(outcome.distance, outcome.time, &replay), Ok(outcome));
+        assert_eq!(outcome, ride(seed).1);
+    }
+
+    #[test]
+    fn a_run_without_a_crash_is_rejected() {
+        let (mut replay, outcome) = ride(7);
+        replay.frames.truncate(replay.frames.len() / 2);
+        assert_eq!(
+            verify(outcome.distance, outcome.time, &replay),
+            Err(VerifyError::NoCrash)
+        );
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let (mut replay, outcome) = ride(7);
+        replay.version = REPLAY_VERSION + 1;
+        assert_eq!(
+            verify(outcome.distance, outcome.time, &replay),
+            Err(VerifyError::UnsupportedVersion(REPLAY_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn the_clock_allows_what_the_game_does() {
+        // a slow frame catches up with two steps, a fast one waits for the next
+        let replay = frames(&[
+            (TIMESTEP, 1),
+            (TIMESTEP * 2., 2),
+            (TIMESTEP * 0.5, 0),
+            (TIMESTEP * 0.5, 1),
+        ]);
+        assert_eq!(check_clock(&replay), Ok(()));
+    }
+
+    #[test]
+    fn the_clock_rejects_extra_steps() {
+        let replay = frames(&[(TIMESTEP, 1), (TIMESTEP, 3), (TIMESTEP, 1)]);
+        assert_eq!(
+            check_clock(&replay),
+            Err(VerifyError::StepsOutOfTime { frame: 1 })
+        );
+        // one step early can be a remainder carried over, but not one on every frame
+        let replay = frames(&[(TIMESTEP, 1), (TIMESTEP, 2), (TIMESTEP, 2)]);
+        assert_eq!(
+            check_clock(&replay),
+            Err(VerifyError::StepsOutOfTime { frame: 2 })
+        );
+    }
+
+    #[test]
+    fn the_clock_rejects_missing_steps() {
+        let replay = frames(&[(TIMESTEP, 1), (TIMESTEP * 3., 1), (TIMESTEP, 1)]);
+        assert_eq!(
+            check_clock(&replay),
+            Err(VerifyError::StepsOutOfTime { frame: 1 })
+        );
+    }
+
+    #[test]
+    fn the_clock_rejects_impossible_frame_times() {
+        for delta_time in [-TIMESTEP, 0., f32::NAN, SUSPEND_FRAME_TIME * 2.] {
+            let replay = frames(&[(TIMESTEP, 1), (delta_time, 0)]);
+            assert_eq!(
+                check_clock(&replay),
+                Err(VerifyError::BadFrameTime { frame: 1 })
+            );
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{
+    constants::*,
+    input::{Binding, Bindings, PlayerInput},
+    settings::Difficulty,
+    sim::Simulation,
+};
+use macroquad::prelude::*;
+
+// each lane shows the bottom half of the world, where the riding happens
+const LANE_TOP: f32 = RESOLUTION_Y * 0.5;
+
+// one side of the split screen race
+#[derive(Clone)]
+pub struct Rider {
+    pub sim: Simulation,
+    pub bindings: Bindings,
+    pub render_target: RenderTarget,
+    pub camera: Camera2D,
+    pub color: Color,
+}
+
+impl Rider {
+    fn new(bindings: Bindings, color: Color, filter: FilterMode) -> Self {
+        let view = lane_view();
+        let render_target = render_target(view.w as u32, view.h as u32);
+        render_target.texture.set_filter(filter);
+        let mut camera = Camera2D::from_display_rect(view);
+        camera.render_target = Some(render_target);
+        Self {
+            sim: Simulation::new(0, Difficulty::Normal, false),
+            bindings,
+            render_target,
+            camera,
+            color,
+        }
+    }
+
+    // the part of the world the lane's camera sees
+    pub fn view(&self) -> Rect {
+        lane_view()
+    }
+}
+
+// the local two player race, both riders on the same seed in lanes of their own
+#[derive(Clone)]
+pub struct Versus {
+    pub seed: u64,
+    pub riders: Vec<Rider>,
+}
+
+impl Versus {
+    pub fn new(filter: FilterMode) -> Self {
+        Self {
+            seed: 0,
+            riders: vec![
+                Rider::new(player_one_bindings(), PALETTE[14], filter),
+                Rider::new(player_two_bindings(), PALETTE[13], filter),
+            ],
+        }
+    }
+
+    pub fn start(&mut self, seed: u64, difficulty: Difficulty, stamina_mode: bool) {
+        self.seed = seed;
+        for rider in self.riders.iter_mut() {
+            rider.sim = Simulation::new(seed, difficulty, stamina_mode);
+        }
+    }
+
+    pub fn set_filter(&self, filter: FilterMode) {
+        for rider in &self.riders {
+            rider.render_target.texture.set_filter(filter);
+        }
+    }
+
+    pub fn tick(&mut self, delta_time: f32) {
+        for rider in self.riders.iter_mut().filter(|rider| !rider.sim.crashed) {
+            let input = PlayerInput::poll(&rider.bindings);
+            rider.sim.tick(&input, delta_time);
+        }
+    }
+
+    // true when a rider crashed on this step
+    pub fn step(&mut self) -> bool {
+        let mut crashed = false;
+        for rider in self.riders.iter_mut().filter(|rider| !rider.sim.crashed) {
+            if rider.sim.step() {
+                rider.sim.player.is_moving = false;
+                crashed = true;
+            }
+        }
+        crashed
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.riders.iter().all(|rider| rider.sim.crashed)
+    }
+
+    // the rider furthest along that is still riding
+    pub fn leader(&self) -> Option<&Simulation> {
+        self.riders
+            .iter()
+            .map(|rider| &rider.sim)
+            .filter(|sim| !sim.crashed)
+            .max_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+}
+
+fn lane_view() -> Rect {
+    Rect::new(0., LANE_TOP, RESOLUTION_X, RESOLUTION_Y - LANE_TOP)
+}
+
+// fixed keys on either side of the keyboard, the rebindable controls stay with solo riding
+fn player_one_bindings() -> Bindings {
+    Bindings {
+        jump: vec![Binding::Key(KeyCode::Space)],
+        pedal: vec![Binding::Key(KeyCode::LeftShift)],
+        brake: vec![Binding::Key(KeyCode::S)],
+        stroke_left: vec![Binding::Key(KeyCode::A)],
+        stroke_right: vec![Binding::Key(KeyCode::D)],
+        rewind: Vec::new(),
+    }
+}
+
+fn player_two_bindings() -> Bindings {
+    Bindings {
+        jump: vec![Binding::Key(KeyCode::Enter)],
+        pedal: vec![Binding::Key(KeyCode::RightShift)],
+        brake: vec![Binding::Key(KeyCode::Down)],
+        stroke_left: vec![Binding::Key(KeyCode::Left)],
+        stroke_right: vec![Binding::Key(KeyCode::Right)],
+        rewind: Vec::new(),
+    }
+}
@@ -0,0 +1,345 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// share of the portrait layout width a finger needs to hit a widget reliably
+const PORTRAIT_TOUCH_TARGET: f32 = 0.09;
+// upright the game image grows sky above the trail, up to this many times as tall as it is wide
+const PORTRAIT_MAX_ASPECT: f32 = 2.4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScaleMode {
+    Letterbox,
+    PixelPerfect,
+    Stretch,
+}
+
+impl ScaleMode {
+    pub fn key(&self) -> &'static str {
+        match self {
+            ScaleMode::Letterbox => "scale.letterbox",
+            ScaleMode::PixelPerfect => "scale.pixel_perfect",
+            ScaleMode::Stretch => "scale.stretch",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ScaleMode::Letterbox => ScaleMode::PixelPerfect,
+            ScaleMode::PixelPerfect => ScaleMode::Stretch,
+            ScaleMode::Stretch => ScaleMode::Letterbox,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        self.next().next()
+    }
+}
+
+// screen pixels hidden behind notches, rounded corners and home indicators
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Insets {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn safe_area_insets() -> Insets {
+    Insets::default()
+}
+
+// read from the css env(safe-area-inset-*) values by wasm/mtb_plugin.js
+#[cfg(target_arch = "wasm32")]
+fn safe_area_insets() -> Insets {
+    extern "C" {
+        fn mtb_safe_area_inset(side: u32) -> f32;
+    }
+    unsafe {
+        Insets {
+            top: mtb_safe_area_inset(0),
+            right: mtb_safe_area_inset(1),
+            bottom: mtb_safe_area_inset(2),
+            left: mtb_safe_area_inset(3),
+        }
+    }
+}
+
+// maps the fixed virtual resolution the game is authored in onto the window
+#[derive(Debug, Clone)]
+pub struct Viewport {
+    pub mode: ScaleMode,
+    pub virtual_size: Vec2,
+    pub screen_size: Vec2,
+    pub insets: Insets,
+    // the game image, the virtual size when level and taller when upright
+    pub game_size: Vec2,
+    // where the game image lands on screen
+    pub rect: Rect,
+    // the space HUD and menus are laid out in, and where it lands on screen
+    pub ui_size: Vec2,
+    pub ui_rect: Rect,
+}
+
+impl Viewport {
+    pub fn new(virtual_size: Vec2, mode: ScaleMode) -> Self {
+        let mut viewport = Self {
+            mode,
+            virtual_size,
+            screen_size: virtual_size,
+            insets: Insets::default(),
+            game_size: virtual_size,
+            rect: Rect::new(0., 0., virtual_size.x, virtual_size.y),
+            ui_size: virtual_size,
+            ui_rect: Rect::new(0., 0., virtual_size.x, virtual_size.y),
+        };
+        viewport.update(mode);
+        viewport
+    }
+
+    pub fn is_portrait(&self) -> bool {
+        self.screen_size.y > self.screen_size.x
+    }
+
+    pub fn update(&mut self, mode: ScaleMode) {
+        self.layout(
+            mode,
+            vec2(screen_width(), screen_height()),
+            safe_area_insets(),
+        );
+    }
+
+    fn layout(&mut self, mode: ScaleMode, screen_size: Vec2, insets: Insets) {
+        self.mode = mode;
+        self.screen_size = screen_size;
+        self.insets = insets;
+        let safe_size = self.safe_size();
+        self.game_size = if self.is_portrait() {
+            // whole pixels, it's the size of the texture the game is drawn into
+            let height = (self.virtual_size.x * safe_size.y / safe_size.x).round();
+            vec2(
+                self.virtual_size.x,
+                height.clamp(
+                    self.virtual_size.y,
+                    self.virtual_size.x * PORTRAIT_MAX_ASPECT,
+                ),
+            )
+        } else {
+            self.virtual_size
+        };
+        self.rect = self.fit(self.game_size);
+
+        // upright the ui gets a narrower, taller space of its own so text stays readable
+        if self.is_portrait() {
+            let width = self.virtual_size.y;
+            self.ui_size = vec2(width, width * self.screen_size.y / self.screen_size.x);
+            self.ui_rect = Rect::new(0., 0., self.screen_size.x, self.screen_size.y);
+        } else {
+            self.ui_size = self.virtual_size;
+            self.ui_rect = self.rect;
+        }
+    }
+
+    fn safe_size(&self) -> Vec2 {
+        vec2(
+            self.screen_size.x - self.insets.left - self.insets.right,
+            self.screen_size.y - self.insets.top - self.insets.bottom,
+        )
+    }
+
+    // where an image of the given virtual size lands on screen in the current mode
+    pub fn fit(&self, size: Vec2) -> Rect {
+        let safe_size = self.safe_size();
+        let fit = (safe_size / size).min_element();
+        let scale = match self.mode {
+            ScaleMode::Letterbox => Vec2::splat(fit),
+            // whole multiples only, and whole fractions when the window is smaller than the game
+            ScaleMode::PixelPerfect if fit >= 1. => Vec2::splat(fit.floor()),
+            ScaleMode::PixelPerfect => Vec2::splat(1. / (1. / fit).ceil()),
+            // stretching a landscape game over an upright phone is unplayable, fit it instead
+            ScaleMode::Stretch if self.is_portrait() => Vec2::splat(fit),
+            ScaleMode::Stretch => self.screen_size / size,
+        };
+        let size = size * scale;
+        let offset = if self.mode == ScaleMode::Stretch && !self.is_portrait() {
+            Vec2::ZERO
+        } else {
+            (vec2(self.insets.left, self.insets.top) + (safe_size - size) * 0.5).round()
+        };
+        Rect::new(offset.x, offset.y, size.x, size.y)
+    }
+
+    // the part of the world the game camera sees, the extra height goes to the sky
+    pub fn game_view(&self) -> Rect {
+        Rect::new(
+            0.,
+            self.virtual_size.y - self.game_size.y,
+            self.game_size.x,
+            self.game_size.y,
+        )
+    }
+
+    pub fn scale(&self) -> Vec2 {
+        vec2(self.ui_rect.w, self.ui_rect.h) / self.ui_size
+    }
+
+    pub fn to_virtual(&self, point: Vec2) -> Vec2 {
+        (point - vec2(self.ui_rect.x, self.ui_rect.y)) / self.scale()
+    }
+
+    pub fn mouse_position(&self) -> Vec2 {
+        let (x, y) = mouse_position();
+        self.to_virtual(vec2(x, y))
+    }
+
+    // the part of the ui space that isn't covered by the screen's insets
+    pub fn safe_area(&self) -> Rect {
+        let top_left = self
+            .to_virtual(vec2(self.insets.left, self.insets.top))
+            .max(Vec2::ZERO);
+        let bottom_right = self
+            .to_virtual(self.screen_size - vec2(self.insets.right, self.insets.bottom))
+            .min(self.ui_size);
+        Rect::new(
+            top_left.x,
+            top_left.y,
+            bottom_right.x - top_left.x,
+            bottom_right.y - top_left.y,
+        )
+    }
+
+    // the game image in ui coordinates
+    pub fn game_area(&self) -> Rect {
+        let top_left = self.to_virtual(vec2(self.rect.x, self.rect.y));
+        let size = vec2(self.rect.w, self.rect.h) / self.scale();
+        Rect::new(top_left.x, top_left.y, size.x, size.y)
+    }
+
+    pub fn touch_target(&self) -> f32 {
+        if self.is_portrait() {
+            self.ui_size.x * PORTRAIT_TOUCH_TARGET
+        } else {
+            0.
+        }
+    }
+
+    // draws in ui coordinates over the whole window, so overlays can still reach into the bars
+    pub fn ui_camera(&self) -> Camera2D {
+        let scale = self.scale();
+        Camera2D::from_display_rect(Rect::new(
+            -self.ui_rect.x / scale.x,
+            -self.ui_rect.y / scale.y,
+            self.screen_size.x / scale.x,
+            self.screen_size.y / scale.y,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn laid_out(mode: ScaleMode, screen_size: Vec2, insets: Insets) -> Viewport {
+        let virtual_size = vec2(1920., 1080.);
+        let mut viewport = Viewport {
+            mode,
+            virtual_size,
+            screen_size: virtual_size,
+            insets: Insets::default(),
+            game_size: virtual_size,
+            rect: Rect::new(0., 0., virtual_size.x, virtual_size.y),
+            ui_size: virtual_size,
+            ui_rect: Rect::new(0., 0., virtual_size.x, virtual_size.y),
+        };
+        viewport.layout(mode, screen_size, insets);
+        viewport
+    }
+
+    fn assert_rect(actual: Rect, expected: Rect) {
+        // within half a screen pixel
+        let close = |a: f32, b: f32| (a - b).abs() < 0.5;
+        assert!(
+            close(actual.x, expected.x)
+                && close(actual.y, expected.y)
+                && close(actual.w, expected.w)
+                && close(actual.h, expected.h),
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn level_screens_keep_the_virtual_size() {
+        let viewport = laid_out(ScaleMode::Letterbox, vec2(1920., 1080.), Insets::default());
+        assert_eq!(viewport.game_size, vec2(1920., 1080.));
+        assert_rect(viewport.rect, Rect::new(0., 0., 1920., 1080.));
+        assert_rect(viewport.game_view(), Rect::new(0., 0., 1920., 1080.));
+
+        // a wider window gets bars at the sides
+        let viewport = laid_out(ScaleMode::Letterbox, vec2(2560., 1080.), Insets::default());
+        assert_rect(viewport.rect, Rect::new(320., 0., 1920., 1080.));
+    }
+
+    #[test]
+    fn pixel_perfect_scales_by_whole_steps() {
+        let viewport = laid_out(
+            ScaleMode::PixelPerfect,
+            vec2(1280., 720.),
+            Insets::default(),
+        );
+        assert_rect(viewport.rect, Rect::new(160., 90., 960., 540.));
+        let viewport = laid_out(
+            ScaleMode::PixelPerfect,
+            vec2(4000., 2200.),
+            Insets::default(),
+        );
+        assert_rect(viewport.rect, Rect::new(80., 20., 3840., 2160.));
+    }
+
+    #[test]
+    fn upright_screens_get_a_taller_game_filling_the_height() {
+        let viewport = laid_out(ScaleMode::Letterbox, vec2(1080., 1920.), Insets::default());
+        let height = 3413.;
+        assert_rect(
+            Rect::new(0., 0., viewport.game_size.x, viewport.game_size.y),
+            Rect::new(0., 0., 1920., height),
+        );
+        assert_rect(viewport.rect, Rect::new(0., 0., 1080., 1920.));
+        // the ground stays at the bottom, the extra height is sky above it
+        assert_rect(
+            viewport.game_view(),
+            Rect::new(0., 1080. - height, 1920., height),
+        );
+        // stretching upright fits the taller game just the same
+        let stretched = laid_out(ScaleMode::Stretch, vec2(1080., 1920.), Insets::default());
+        assert_rect(stretched.rect, viewport.rect);
+        // races keep their level lanes and letterbox them
+        assert_rect(
+            viewport.fit(viewport.virtual_size),
+            Rect::new(0., 656., 1080., 607.5),
+        );
+    }
+
+    #[test]
+    fn very_tall_screens_stop_growing_the_sky() {
+        let viewport = laid_out(ScaleMode::Letterbox, vec2(600., 2000.), Insets::default());
+        assert_eq!(viewport.game_size, vec2(1920., 1920. * PORTRAIT_MAX_ASPECT));
+        assert_rect(viewport.rect, Rect::new(0., 280., 600., 1440.));
+    }
+
+    #[test]
+    fn insets_are_kept_clear() {
+        let insets = Insets {
+            top: 100.,
+            bottom: 50.,
+            ..Default::default()
+        };
+        let viewport = laid_out(ScaleMode::Letterbox, vec2(1080., 1920.), insets);
+        assert_rect(
+            Rect::new(0., 0., viewport.game_size.x, viewport.game_size.y),
+            Rect::new(0., 0., 1920., 3147.),
+        );
+        assert_rect(viewport.rect, Rect::new(0., 100., 1080., 1770.));
+    }
+}
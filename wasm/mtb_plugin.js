@@ -0,0 +1,254 @@
+"use strict";
+
+// Browser side of the game's native hooks, registered with the miniquad loader.
+(function () {
+  const encoder = new TextEncoder();
+
+  function read_string(ptr, len) {
+    return UTF8ToString(ptr, len);
+  }
+
+  function write_bytes(bytes, ptr, len) {
+    new Uint8Array(wasm_memory.buffer, ptr, len).set(bytes.subarray(0, len));
+  }
+
+  function storage_key(ptr, len) {
+    return "mtb/" + read_string(ptr, len);
+  }
+
+  // files picked for import, waiting for the game to take them
+  const imports = [];
+
+  function request_import() {
+    const input = document.createElement("input");
+    input.type = "file";
+    input.accept = ".json,application/json";
+    input.multiple = true;
+    input.addEventListener("change", function () {
+      for (const file of input.files) {
+        file.text().then(function (text) {
+          imports.push(encoder.encode(text));
+        });
+      }
+    });
+    input.click();
+  }
+
+  function export_file(name, data) {
+    const url = URL.createObjectURL(new Blob([data], { type: "application/json" }));
+    const link = document.createElement("a");
+    link.href = url;
+    link.download = name;
+    link.click();
+    setTimeout(function () {
+      URL.revokeObjectURL(url);
+    }, 0);
+  }
+
+  // finished leaderboard requests, waiting for the game to poll them
+  const responses = [];
+
+  function http_request(id, method, url, body) {
+    const options = { method: method };
+    if (method !== "GET") {
+      options.headers = { "Content-Type": "application/json" };
+      options.body = body;
+    }
+    fetch(url, options)
+      .then(function (response) {
+        return response.text().then(function (text) {
+          responses.push({ id: id, status: response.status, body: encoder.encode(text) });
+        });
+      })
+      .catch(function (e) {
+        responses.push({ id: id, status: 0, body: encoder.encode(String(e)) });
+      });
+  }
+
+  // set when the page is hidden or loses focus, cleared once the game has seen it
+  let interrupted = false;
+
+  function is_page_active() {
+    return document.visibilityState === "visible" && document.hasFocus();
+  }
+
+  // the audio context belongs to mq_js_bundle.js, suspending it silences everything at once
+  function set_audio_suspended(suspended) {
+    if (typeof audio_context === "undefined" || !audio_context) {
+      return;
+    }
+    if (suspended) {
+      audio_context.suspend();
+    } else {
+      audio_context.resume();
+    }
+  }
+
+  // a hidden page gets no more animation frames and may be closed without ever showing again,
+  // so the game gets one frame right away to pause and save the ride
+  function interrupt_now() {
+    interrupted = true;
+    if (typeof wasm_exports !== "undefined" && wasm_exports) {
+      wasm_exports.frame();
+    }
+  }
+
+  document.addEventListener("visibilitychange", function () {
+    if (document.hidden) {
+      interrupt_now();
+    }
+    set_audio_suspended(document.hidden);
+  });
+
+  window.addEventListener("pagehide", function () {
+    interrupt_now();
+    set_audio_suspended(true);
+  });
+
+  window.addEventListener("pageshow", function () {
+    set_audio_suspended(document.hidden);
+  });
+
+  window.addEventListener("blur", function () {
+    interrupted = true;
+  });
+
+  // css env() values can only be read back through an element that uses them
+  const safe_area_probe = document.createElement("div");
+  safe_area_probe.style.cssText =
+    "position: fixed; visibility: hidden; pointer-events: none;" +
+    "padding: env(safe-area-inset-top) env(safe-area-inset-right)" +
+    " env(safe-area-inset-bottom) env(safe-area-inset-left);";
+  if (document.body) {
+    document.body.appendChild(safe_area_probe);
+  } else {
+    document.addEventListener("DOMContentLoaded", function () {
+      document.body.appendChild(safe_area_probe);
+      safe_area = null;
+    });
+  }
+
+  // in canvas pixels, the same units the game measures the screen in. reading them back forces a
+  // style recalculation, so they're only read again once the page has been resized or turned
+  let safe_area = null;
+  window.addEventListener("resize", function () {
+    safe_area = null;
+  });
+  window.addEventListener("orientationchange", function () {
+    safe_area = null;
+  });
+
+  function safe_area_inset(side) {
+    if (safe_area === null) {
+      const style = getComputedStyle(safe_area_probe);
+      const canvas = document.getElementById("glcanvas");
+      const pixel_ratio = canvas && canvas.clientWidth > 0 ? canvas.width / canvas.clientWidth : 1;
+      safe_area = [style.paddingTop, style.paddingRight, style.paddingBottom, style.paddingLeft].map(
+        function (padding) {
+          return (parseFloat(padding) || 0) * pixel_ratio;
+        }
+      );
+    }
+    return safe_area[side];
+  }
+
+  function register_plugin(importObject) {
+    importObject.env.mtb_storage_set = function (key, key_len, data, data_len) {
+      try {
+        localStorage.setItem(storage_key(key, key_len), read_string(data, data_len));
+        return 1;
+      } catch (e) {
+        console.error(e);
+        return 0;
+      }
+    };
+
+    importObject.env.mtb_storage_len = function (key, key_len) {
+      const value = localStorage.getItem(storage_key(key, key_len));
+      return value === null ? -1 : encoder.encode(value).length;
+    };
+
+    importObject.env.mtb_storage_get = function (key, key_len, buffer, buffer_len) {
+      const value = localStorage.getItem(storage_key(key, key_len)) || "";
+      write_bytes(encoder.encode(value), buffer, buffer_len);
+    };
+
+    importObject.env.mtb_storage_remove = function (key, key_len) {
+      localStorage.removeItem(storage_key(key, key_len));
+    };
+
+    importObject.env.mtb_export = function (name, name_len, data, data_len) {
+      export_file(read_string(name, name_len), read_string(data, data_len));
+    };
+
+    importObject.env.mtb_request_import = function () {
+      request_import();
+    };
+
+    importObject.env.mtb_import_len = function () {
+      return imports.length === 0 ? -1 : imports[0].length;
+    };
+
+    importObject.env.mtb_import_take = function (buffer, buffer_len) {
+      write_bytes(imports.shift(), buffer, buffer_len);
+    };
+
+    importObject.env.mtb_http_request = function (
+      id,
+      method,
+      method_len,
+      url,
+      url_len,
+      body,
+      body_len
+    ) {
+      http_request(
+        id,
+        read_string(method, method_len),
+        read_string(url, url_len),
+        read_string(body, body_len)
+      );
+    };
+
+    importObject.env.mtb_http_response_id = function () {
+      return responses.length === 0 ? -1 : responses[0].id;
+    };
+
+    importObject.env.mtb_http_response_status = function () {
+      return responses[0].status;
+    };
+
+    importObject.env.mtb_http_response_len = function () {
+      return responses[0].body.length;
+    };
+
+    importObject.env.mtb_http_response_take = function (buffer, buffer_len) {
+      write_bytes(responses.shift().body, buffer, buffer_len);
+    };
+
+    importObject.env.mtb_take_interrupted = function () {
+      const was_interrupted = interrupted;
+      interrupted = false;
+      return was_interrupted ? 1 : 0;
+    };
+
+    importObject.env.mtb_page_active = function () {
+      return is_page_active() ? 1 : 0;
+    };
+
+    importObject.env.mtb_safe_area_inset = function (side) {
+      return safe_area_inset(side);
+    };
+
+    // writes as much of the list as fits and returns how many bytes that was
+    importObject.env.mtb_preferred_languages = function (buffer, buffer_len) {
+      const languages = navigator.languages || [navigator.language || ""];
+      const bytes = encoder.encode(languages.join(","));
+      const len = Math.min(bytes.length, buffer_len);
+      write_bytes(bytes, buffer, len);
+      return len;
+    };
+  }
+
+  miniquad_add_plugin({ register_plugin, version: "0.1.0", name: "mtb" });
+})();